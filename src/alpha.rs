@@ -0,0 +1,158 @@
+//! Alpha 预乘及反预乘。
+//!
+//! 预乘由硬件完成：先将目标清为透明黑，再以非预乘源的 SRC_OVER 模式混合，
+//! 得到 `c * a / 255`；原地转换时以临时的透明黑图像作为混合背景。硬件无法执行除法，因此反预乘只能在已映射到用户空间的图像上由 CPU 完成。
+use super::geom::{bounds_of, invalid_input};
+use super::{soft, Rga, RgaInfo, RgaPixelFormat, RgaRectBuilder};
+use std::io;
+
+/// 非预乘源的 SRC_OVER 混合模式。
+pub(crate) const BLEND_SRC_OVER_STRAIGHT: u32 = 0x0405;
+
+impl Rga {
+    /// 将非预乘 Alpha 的 `src` 图像转换为预乘形式写入 `dst` 图像，支持原地转换。
+    pub fn premultiply(&self, src: &RgaInfo, dst: &mut RgaInfo) -> Result<(), io::Error> {
+        check_alpha(src, dst)?;
+        let src = RgaInfo {
+            blend: BLEND_SRC_OVER_STRAIGHT,
+            ..*src
+        };
+        if !same_memory(&src, dst) {
+            self.fill(dst, 0)?;
+            return self.blit(&src, dst, None);
+        }
+        // 先清空目标会破坏源图像，改为与透明黑背景混合后写入目标。
+        let rect = RgaRectBuilder::new()
+            .size(dst.rect.width, dst.rect.height)
+            .format(RgaPixelFormat::from_raw(dst.rect.format))
+            .build();
+        let mut buf = self.scratch.take(&rect)?;
+        buf.fill(0);
+        let mut background = buf.info(rect, dst);
+        self.blit(&src, dst, Some(&mut background))
+    }
+
+    /// 将预乘 Alpha 的 `src` 图像还原为非预乘形式写入 `dst` 图像。
+    ///
+    /// 两幅图像均须已映射到用户空间，否则返回 `Unsupported` 错误。
+    pub fn unpremultiply(&self, src: &RgaInfo, dst: &mut RgaInfo) -> Result<(), io::Error> {
+        check_alpha(src, dst)?;
+        let unmapped = || {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "unpremultiply requires images mapped to user space",
+            )
+        };
+        // 先复制源图像，以支持原地转换。
+        let src_buf = unsafe { soft::info_bytes_mut(src) }
+            .ok_or_else(unmapped)?
+            .to_vec();
+        let dst_buf = unsafe { soft::info_bytes_mut(dst) }.ok_or_else(unmapped)?;
+        soft::unpremultiply(&src_buf, &src.rect, dst_buf, &dst.rect);
+        Ok(())
+    }
+}
+
+/// 当两幅图像位于同一块内存时返回 true。
+///
+/// `RgaInfo::default()` 的 `fd` 为 0，因此仅比较正数的文件描述符。
+fn same_memory(a: &RgaInfo, b: &RgaInfo) -> bool {
+    (a.fd > 0 && a.fd == b.fd)
+        || (!a.virAddr.is_null() && a.virAddr == b.virAddr)
+        || (!a.phyAddr.is_null() && a.phyAddr == b.phyAddr)
+}
+
+/// 检查两幅图像的大小一致且格式均带有 Alpha 分量。
+fn check_alpha(src: &RgaInfo, dst: &RgaInfo) -> Result<(), io::Error> {
+    let (s, d) = (bounds_of(src)?, bounds_of(dst)?);
+    if (s.width, s.height) != (d.width, d.height) {
+        return Err(invalid_input("source and destination sizes differ"));
+    }
+    for info in [src, dst] {
+        let fmt = RgaPixelFormat::from_raw(info.rect.format);
+        if !fmt.has_alpha() || !soft::is_supported(fmt) {
+            return Err(invalid_input(&format!(
+                "format {:?} has no alpha channel",
+                fmt
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::soft::{premultiply_pixel, unpremultiply_pixel, SoftImage};
+
+    #[test]
+    fn test_premultiply_pixel() {
+        assert_eq!(premultiply_pixel([255, 128, 0, 128]), [128, 64, 0, 128]);
+        assert_eq!(premultiply_pixel([200, 100, 50, 255]), [200, 100, 50, 255]);
+        assert_eq!(premultiply_pixel([200, 100, 50, 0]), [0, 0, 0, 0]);
+        assert_eq!(unpremultiply_pixel([128, 64, 0, 128]), [255, 128, 0, 128]);
+        assert_eq!(unpremultiply_pixel([9, 9, 9, 0]), [0, 0, 0, 0]);
+        assert_eq!(unpremultiply_pixel([200, 10, 0, 100]), [255, 26, 0, 100]);
+        // 8 位下反预乘后再预乘得到原值。
+        for a in 1..=255u8 {
+            for c in 0..=a {
+                let px = [c, c, c, a];
+                assert_eq!(premultiply_pixel(unpremultiply_pixel(px)), px);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unpremultiply_in_place() {
+        let mut img = SoftImage::new(4, 2, RgaPixelFormat::Rgba8888);
+        img.put(1, 1, [64, 32, 16, 128]);
+        let mut info = RgaInfo {
+            fd: -1,
+            virAddr: img.data.as_mut_ptr() as *mut _,
+            rect: img.rect,
+            format: img.rect.format,
+            ..Default::default()
+        };
+        let src = info;
+        let rga = Rga::new().unwrap();
+        rga.unpremultiply(&src, &mut info).unwrap();
+        assert_eq!(img.get(1, 1), [128, 64, 32, 128]);
+        assert_eq!(img.get(0, 0), [0, 0, 0, 0]);
+
+        let rgb = SoftImage::new(4, 2, RgaPixelFormat::Rgb888);
+        let bad = RgaInfo {
+            rect: rgb.rect,
+            ..src
+        };
+        let err = rga.premultiply(&bad, &mut info).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_premultiply_in_place() {
+        let rga = Rga::new().unwrap();
+        let mut img = SoftImage::new(4, 2, RgaPixelFormat::Rgba8888);
+        let mut other = SoftImage::new(4, 2, RgaPixelFormat::Rgba8888);
+        let info = |img: &mut SoftImage| RgaInfo {
+            fd: -1,
+            virAddr: img.data.as_mut_ptr() as *mut _,
+            rect: img.rect,
+            format: img.rect.format,
+            ..Default::default()
+        };
+        let src = info(&mut img);
+        let mut dst = info(&mut other);
+        assert!(!same_memory(&src, &dst));
+        // 未设置文件描述符的不同图像不视为同一块内存。
+        let unset = |info: RgaInfo| RgaInfo { fd: 0, ..info };
+        assert!(!same_memory(&unset(src), &unset(dst)));
+        rga.premultiply(&src, &mut dst).unwrap();
+        assert_eq!(rga.scratch.cached(), 0);
+
+        // 原地转换不先清空图像，而是与临时的透明背景混合。
+        let mut dst = src;
+        assert!(same_memory(&src, &dst));
+        rga.premultiply(&src, &mut dst).unwrap();
+        assert_eq!(rga.scratch.cached(), 1);
+    }
+}
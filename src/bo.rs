@@ -1,6 +1,6 @@
 //! 内存缓冲对象。
 //!
-use super::{ffi, Rga, RgaPixelFormat, RgaRect};
+use super::{ffi, MemoryLayout, Rga, RgaPixelFormat, RgaRect};
 use std::io;
 use std::sync::Arc;
 
@@ -9,7 +9,7 @@ use std::sync::Arc;
 pub struct RgaBuffer {
     bo: ffi::bo_t,
     mapped: bool,
-    _rga: Arc<Rga>,
+    _rga: Option<Arc<Rga>>,
 }
 
 impl RgaBuffer {
@@ -28,6 +28,19 @@ impl RgaBuffer {
     /// bo.unmap();
     /// ```
     pub fn new(rga: Arc<Rga>, width: i32, height: i32, bpp: i32) -> Result<Self, io::Error> {
+        Self::alloc(Some(rga), width, height, bpp)
+    }
+
+    /// 创建一个不持有 [`Rga`] 引用且已映射到用户空间的缓冲对象，供 [`Rga`] 内部缓存使用。
+    ///
+    /// 调用者需保证其在 [`Rga`] 释放之前释放。
+    pub(crate) fn detached_mapped(width: i32, height: i32, bpp: i32) -> Result<Self, io::Error> {
+        let mut bo = Self::alloc(None, width, height, bpp)?;
+        bo.map()?;
+        Ok(bo)
+    }
+
+    fn alloc(rga: Option<Arc<Rga>>, width: i32, height: i32, bpp: i32) -> Result<Self, io::Error> {
         unsafe {
             let mut bo: ffi::bo_t = Default::default();
             match ffi::c_RkRgaGetAllocBuffer(&mut bo, width, height, bpp) {
@@ -50,20 +63,29 @@ impl RgaBuffer {
 
     /// 为指定的 RGA 图像区域创建一个 RGA 内存缓冲对象。
     pub fn with_rect(rga: Arc<Rga>, rect: &RgaRect) -> Result<Self, io::Error> {
-        let pixfmt = RgaPixelFormat::from(unsafe {
-            std::mem::transmute::<i32, ffi::RgaSURF_FORMAT>(rect.format)
-        });
+        let pixfmt = RgaPixelFormat::from_raw(rect.format);
         Self::new(rga, rect.width, rect.height, pixfmt.bits_per_pixel() as i32)
     }
 
     /// 为指定的 RGA 图像区域创建一个 RGA 内存缓冲对象。
     pub fn with_rect_mapped(rga: Arc<Rga>, rect: &RgaRect) -> Result<Self, io::Error> {
-        let pixfmt = RgaPixelFormat::from(unsafe {
-            std::mem::transmute::<i32, ffi::RgaSURF_FORMAT>(rect.format)
-        });
+        let pixfmt = RgaPixelFormat::from_raw(rect.format);
         Self::new_mapped(rga, rect.width, rect.height, pixfmt.bits_per_pixel() as i32)
     }
 
+    /// 为指定内存布局的 RGA 图像区域创建一个 RGA 内存缓冲对象，大小包含 AFBC 头部。
+    pub fn with_layout(
+        rga: Arc<Rga>,
+        rect: &RgaRect,
+        layout: MemoryLayout,
+    ) -> Result<Self, io::Error> {
+        let fmt = RgaPixelFormat::from_raw(rect.format);
+        let size = layout.buffer_size(rect.wstride, rect.hstride, fmt);
+        let pitch = (rect.wstride.max(1) as usize * fmt.bits_per_pixel()).div_ceil(8);
+        let rows = size.div_ceil(pitch);
+        Self::new(rga, pitch as i32, rows as i32, 8)
+    }
+
     /// 返回文件描述符。
     ///
     /// # Note
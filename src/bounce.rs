@@ -0,0 +1,448 @@
+//! 未对齐图像的自动对齐。
+//!
+//! 启用 [`Rga::set_auto_align`] 后，若映射到用户空间的图像地址、内存跨度不满足硬件要求，
+//! 或 YUV 图像区域未按色度采样对齐，操作前先将图像复制到对齐的临时 [`RgaBuffer`](crate::RgaBuffer) 中，
+//! 操作完成后再将目标图像复制回原内存。
+//!
+//! 临时图像的宽高补齐到色度采样的整数倍，补齐部分重复边缘像素；
+//! 经临时缓冲区的操作总是同步执行，复制前先在调用线程等待输入栅栏触发。
+use super::scratch::{ScratchBuffer, ScratchPool};
+use super::soft::{self, Plane};
+use super::{ffi, Rga, RgaInfo, RgaPixelFormat, RgaRect, RgaRectBuilder};
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 各平面起始地址的对齐字节数。
+const ADDR_ALIGN: usize = 16;
+/// 各平面一行字节跨度的对齐字节数。
+const STRIDE_ALIGN: usize = 4;
+/// 临时图像内存跨度的对齐像素数。
+const BOUNCE_STRIDE_ALIGN: i32 = 16;
+
+/// 返回可逐字节复制的图像的各个平面，其它格式返回 None。
+fn planes(rect: &RgaRect) -> Option<Vec<Plane>> {
+    let fmt = RgaPixelFormat::from_raw(rect.format);
+    if fmt.is_yuv() {
+        return soft::yuv_planes(rect);
+    }
+    let bpp = fmt.bits_per_pixel();
+    if bpp == 0 || !bpp.is_multiple_of(8) {
+        return None;
+    }
+    Some(vec![Plane {
+        offset: 0,
+        stride: rect.wstride as usize * bpp / 8,
+        div: (1, 1),
+        channels: bpp / 8,
+    }])
+}
+
+/// 当 `info` 描述的图像仅以虚拟地址给出且为线性布局时返回 true。
+fn is_cpu_image(info: &RgaInfo) -> bool {
+    #[cfg(feature = "v1_4_0")]
+    if info.rd_mode > 1 {
+        return false;
+    }
+    #[cfg(feature = "v1_7_2")]
+    if info.handle > 0 {
+        return false;
+    }
+    info.fd < 0 && info.phyAddr.is_null() && !info.virAddr.is_null()
+}
+
+/// 当 `info` 描述的图像不满足硬件对齐要求且可经临时缓冲区修正时返回 true。
+///
+/// 检查各平面的起始地址、字节跨度，以及 YUV 图像区域是否按色度采样对齐。
+pub fn needs_bounce(info: &RgaInfo) -> bool {
+    if !is_cpu_image(info) {
+        return false;
+    }
+    let r = &info.rect;
+    let planes = match planes(r) {
+        Some(planes) => planes,
+        None => return false,
+    };
+    let addr = info.virAddr as usize;
+    let misaligned = planes.iter().any(|p| {
+        !(addr + p.offset).is_multiple_of(ADDR_ALIGN) || !p.stride.is_multiple_of(STRIDE_ALIGN)
+    });
+    let (dx, dy) = RgaPixelFormat::from_raw(r.format)
+        .chroma_subsampling()
+        .unwrap_or((1, 1));
+    misaligned
+        || r.xoffset % dx != 0
+        || r.width % dx != 0
+        || r.yoffset % dy != 0
+        || r.height % dy != 0
+}
+
+/// 返回容纳 `rect` 区域的对齐临时图像区域。
+fn bounce_rect(rect: &RgaRect) -> RgaRect {
+    let fmt = RgaPixelFormat::from_raw(rect.format);
+    let (dx, dy) = fmt.chroma_subsampling().unwrap_or((1, 1));
+    let round = |v: i32, a: i32| (v + a - 1) / a * a;
+    let (w, h) = (round(rect.width, dx), round(rect.height, dy));
+    RgaRectBuilder::new()
+        .size(w, h)
+        .stride(round(w, BOUNCE_STRIDE_ALIGN), h)
+        .format(fmt)
+        .build()
+}
+
+/// 将 `src` 图像的 `s` 区域复制到 `dst` 图像的 `d` 区域，`d` 超出 `s` 的部分重复边缘采样。
+fn copy_region(src: &[u8], s: &RgaRect, dst: &mut [u8], d: &RgaRect) {
+    let (Some(sp), Some(dp)) = (planes(s), planes(d)) else {
+        return;
+    };
+    for (sp, dp) in sp.iter().zip(&dp) {
+        let (hx, vy) = sp.div;
+        let n = sp.channels;
+        // 返回区域在平面中覆盖的采样范围。
+        let span = |r: &RgaRect| {
+            let (x0, y0) = (r.xoffset / hx, r.yoffset / vy);
+            let (x1, y1) = (
+                (r.xoffset + r.width - 1) / hx,
+                (r.yoffset + r.height - 1) / vy,
+            );
+            (
+                x0 as usize,
+                y0 as usize,
+                (x1 - x0 + 1) as usize,
+                (y1 - y0 + 1) as usize,
+            )
+        };
+        let (sx, sy, sw, sh) = span(s);
+        let (dx, dy, dw, dh) = span(d);
+        let w = sw.min(dw);
+        for row in 0..dh {
+            let so = sp.offset + (sy + row.min(sh - 1)) * sp.stride + sx * n;
+            let doff = dp.offset + (dy + row) * dp.stride + dx * n;
+            let line = &mut dst[doff..doff + dw * n];
+            line[..w * n].copy_from_slice(&src[so..so + w * n]);
+            for i in w..dw {
+                line.copy_within((w - 1) * n..w * n, i * n);
+            }
+        }
+    }
+}
+
+/// 等待操作信息中的输入栅栏触发，使 CPU 读到生产者写入的图像。
+#[cfg(feature = "v1_4_0")]
+fn wait_in_fence(info: &RgaInfo) -> Result<(), io::Error> {
+    if info.in_fence_fd > 0 {
+        let fd = unsafe { std::os::fd::BorrowedFd::borrow_raw(info.in_fence_fd) };
+        super::RgaFence::from(fd.try_clone_to_owned()?).wait(None)?;
+    }
+    Ok(())
+}
+
+/// 一个缓存对齐临时缓冲区的池，并记录经临时缓冲区复制的次数。
+#[derive(Debug, Default)]
+pub(crate) struct BouncePool {
+    bufs: ScratchPool,
+    copies: AtomicU64,
+}
+
+impl BouncePool {
+    /// 返回缓存的缓冲区数量。
+    #[cfg(test)]
+    pub(crate) fn cached(&self) -> usize {
+        self.bufs.cached()
+    }
+
+    /// 释放所有缓存的缓冲区。
+    pub(crate) fn clear(&self) {
+        self.bufs.clear();
+    }
+}
+
+/// 一个准备好参与操作的图像，需要时由临时缓冲区替代原图像，离开作用域时归还缓冲区。
+struct Staged<'a> {
+    pool: &'a BouncePool,
+    buf: Option<ScratchBuffer<'a>>,
+    info: RgaInfo,
+}
+
+impl Staged<'_> {
+    /// 当图像由临时缓冲区替代时，将其复制回 `orig` 图像。
+    fn copy_back(&self, orig: &RgaInfo) {
+        if let Some(buf) = &self.buf {
+            if let Some(out) = unsafe { soft::info_bytes_mut(orig) } {
+                copy_region(buf, &self.info.rect, out, &orig.rect);
+                self.pool.copies.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl Rga {
+    /// 当启用自动对齐时返回 true。
+    pub fn auto_align(&self) -> bool {
+        self.auto_align.load(Ordering::Relaxed)
+    }
+
+    /// 启用或禁用自动对齐，默认禁用。
+    ///
+    /// 启用后，不满足硬件对齐要求的图像经对齐的临时缓冲区参与操作，参见 [`needs_bounce`]。
+    pub fn set_auto_align(&self, enable: bool) {
+        self.auto_align.store(enable, Ordering::Relaxed);
+    }
+
+    /// 返回自动对齐复制图像的累计次数，复制到临时缓冲区及复制回原图像各计一次。
+    pub fn bounce_count(&self) -> u64 {
+        self.bounce.copies.load(Ordering::Relaxed)
+    }
+
+    /// 将自动对齐复制图像的累计次数清零。
+    pub fn reset_bounce_count(&self) {
+        self.bounce.copies.store(0, Ordering::Relaxed);
+    }
+
+    /// 准备参与同步操作的图像，`copy` 为 true 时将原图像内容复制到临时缓冲区。
+    ///
+    /// 调用者需已等待图像的输入栅栏。
+    fn stage(&self, info: &RgaInfo, copy: bool) -> Result<Staged<'_>, io::Error> {
+        let info = RgaInfo {
+            sync_mode: ffi::RGA_BLIT_SYNC as i32,
+            ..*info
+        };
+        #[cfg(feature = "v1_4_0")]
+        let info = RgaInfo {
+            in_fence_fd: -1,
+            ..info
+        };
+        if !needs_bounce(&info) {
+            return Ok(Staged {
+                pool: &self.bounce,
+                buf: None,
+                info,
+            });
+        }
+        let rect = bounce_rect(&info.rect);
+        let mut buf = self.bounce.bufs.take(&rect)?;
+        if copy {
+            if let Some(src) = unsafe { soft::info_bytes_mut(&info) } {
+                copy_region(src, &info.rect, &mut buf, &rect);
+                self.bounce.copies.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        let staged = RgaInfo {
+            fd: buf.fd(),
+            virAddr: buf.as_mut_ptr() as *mut _,
+            phyAddr: std::ptr::null_mut(),
+            mmuFlag: 1,
+            rect,
+            format: rect.format,
+            ..info
+        };
+        #[cfg(feature = "v1_4_0")]
+        let staged = RgaInfo {
+            out_fence_fd: -1,
+            ..staged
+        };
+        Ok(Staged {
+            pool: &self.bounce,
+            buf: Some(buf),
+            info: staged,
+        })
+    }
+
+    /// 执行比特操作，不满足对齐要求的图像经临时缓冲区参与操作。
+    pub(crate) fn blit_bounced(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        extra_src: Option<&mut RgaInfo>,
+    ) -> Result<(), io::Error> {
+        if !needs_bounce(src)
+            && !needs_bounce(dst)
+            && !extra_src.as_deref().is_some_and(needs_bounce)
+        {
+            return self.blit_raw(src, dst, extra_src);
+        }
+        #[cfg(feature = "v1_4_0")]
+        {
+            wait_in_fence(src)?;
+            wait_in_fence(dst)?;
+            if let Some(x) = extra_src.as_deref() {
+                wait_in_fence(x)?;
+            }
+        }
+        let s = self.stage(src, true)?;
+        let mut d = self.stage(dst, true)?;
+        let mut x = match extra_src {
+            Some(x) => Some(self.stage(x, true)?),
+            None => None,
+        };
+        self.blit_raw(&s.info, &mut d.info, x.as_mut().map(|x| &mut x.info))?;
+        d.copy_back(dst);
+        Ok(())
+    }
+
+    /// 填充图像，不满足对齐要求的图像经临时缓冲区参与操作。
+    pub(crate) fn fill_bounced(&self, dst: &RgaInfo, color: u32) -> Result<(), io::Error> {
+        if !needs_bounce(dst) {
+            return self.fill_raw(dst, color);
+        }
+        #[cfg(feature = "v1_4_0")]
+        wait_in_fence(dst)?;
+        let d = self.stage(dst, false)?;
+        self.fill_raw(&d.info, color)?;
+        d.copy_back(dst);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RgaBuffer;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    fn info_of(data: &mut [u8], rect: RgaRect) -> RgaInfo {
+        RgaInfo {
+            fd: -1,
+            virAddr: data.as_mut_ptr() as *mut _,
+            rect,
+            format: rect.format,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_needs_bounce() {
+        let mut data = vec![0u8; 64 * 64 * 4 + 16];
+        let offset = (ADDR_ALIGN - data.as_ptr() as usize % ADDR_ALIGN) % ADDR_ALIGN;
+        let aligned = &mut data[offset..];
+        let rect = |x, y, w, h, ws, fmt| {
+            RgaRectBuilder::new()
+                .offset(x, y)
+                .size(w, h)
+                .stride(ws, 32)
+                .format(fmt)
+                .build()
+        };
+        use RgaPixelFormat::*;
+        assert!(!needs_bounce(&info_of(
+            aligned,
+            rect(0, 0, 32, 32, 32, Rgb888)
+        )));
+        assert!(needs_bounce(&info_of(
+            aligned,
+            rect(0, 0, 31, 32, 31, Rgb888)
+        )));
+        assert!(!needs_bounce(&info_of(
+            aligned,
+            rect(0, 0, 31, 32, 32, Rgba8888)
+        )));
+        assert!(needs_bounce(&info_of(
+            &mut aligned[4..],
+            rect(0, 0, 32, 32, 32, Rgba8888)
+        )));
+        assert!(!needs_bounce(&info_of(
+            aligned,
+            rect(2, 2, 30, 30, 32, YCbCr420sp)
+        )));
+        assert!(needs_bounce(&info_of(
+            aligned,
+            rect(1, 0, 30, 30, 32, YCbCr420sp)
+        )));
+        assert!(needs_bounce(&info_of(
+            aligned,
+            rect(0, 0, 31, 30, 32, YCbCr420sp)
+        )));
+        // 以文件描述符给出的图像无法复制。
+        let dma = RgaInfo {
+            fd: 3,
+            ..info_of(aligned, rect(1, 0, 30, 30, 32, YCbCr420sp))
+        };
+        assert!(!needs_bounce(&dma));
+    }
+
+    #[test]
+    fn test_copy_region_yuv() {
+        let rect = RgaRectBuilder::new()
+            .offset(1, 1)
+            .size(3, 3)
+            .stride(8, 6)
+            .format(RgaPixelFormat::YCbCr420sp)
+            .build();
+        let mut src: Vec<u8> = (0..soft::image_len(&rect)).map(|i| i as u8).collect();
+        let b = bounce_rect(&rect);
+        assert_eq!((b.width, b.height, b.wstride, b.hstride), (4, 4, 16, 4));
+        let mut tmp = vec![0u8; soft::image_len(&b)];
+        copy_region(&src, &rect, &mut tmp, &b);
+        // 亮度行重复最后一个像素，末行重复最后一行。
+        assert_eq!(&tmp[..4], &[9, 10, 11, 11]);
+        assert_eq!(&tmp[48..52], &[25, 26, 27, 27]);
+        // 色度覆盖第 0、1 列采样及第 0、1 行。
+        assert_eq!(&tmp[64..68], &[48, 49, 50, 51]);
+        assert_eq!(&tmp[80..84], &[56, 57, 58, 59]);
+
+        let orig = src.clone();
+        copy_region(&tmp, &b, &mut src, &rect);
+        assert_eq!(src, orig);
+    }
+
+    /// 返回描述 `bo` 中内存跨度为 `wstride` 的 `Rgb888` 图像的操作信息。
+    fn rgb888_in(bo: &mut RgaBuffer, w: i32, h: i32, wstride: i32) -> RgaInfo {
+        let rect = RgaRectBuilder::new()
+            .size(w, h)
+            .stride(wstride, h)
+            .format(RgaPixelFormat::Rgb888)
+            .build();
+        info_of(bo.as_mut_slice(), rect)
+    }
+
+    #[test]
+    fn test_auto_align_fill() {
+        let rga = std::sync::Arc::new(Rga::new().unwrap());
+        let mut bo = rga.alloc_buffer(64, 4, 32).unwrap();
+        bo.map().unwrap();
+        // 每行 21 字节的跨度不满足硬件要求。
+        let info = rgb888_in(&mut bo, 7, 3, 7);
+        assert!(needs_bounce(&info));
+
+        rga.set_auto_align(true);
+        rga.fill(&info, 0).unwrap();
+        assert_eq!(rga.bounce_count(), 1);
+        let mut dst = info;
+        rga.blit(&info, &mut dst, None).unwrap();
+        assert_eq!(rga.bounce_count(), 4);
+        assert_eq!(rga.bounce.cached(), 2);
+
+        rga.reset_bounce_count();
+        rga.release_scratch();
+        assert_eq!((rga.bounce_count(), rga.bounce.cached()), (0, 0));
+    }
+
+    #[cfg(feature = "v1_4_0")]
+    #[test]
+    fn test_auto_align_waits_in_fence() {
+        use crate::RgaFence;
+        use std::time::Duration;
+
+        let rga = std::sync::Arc::new(Rga::new().unwrap());
+        let mut bo = rga.alloc_buffer(64, 4, 32).unwrap();
+        bo.map().unwrap();
+        let src = rgb888_in(&mut bo, 7, 3, 7);
+        let mut dst = src;
+        rga.set_auto_align(true);
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let fence = unsafe { RgaFence::from_raw_fd(fds[0]) };
+        let writer = unsafe { OwnedFd::from_raw_fd(fds[1]) };
+        // 生产者写入图像后才触发栅栏，复制回的结果须包含写入的内容。
+        let addr = bo.as_mut_ptr::<u8>() as usize;
+        let producer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            unsafe { *(addr as *mut u8) = 0x5a };
+            let n = unsafe { libc::write(writer.as_raw_fd(), [1u8].as_ptr().cast(), 1) };
+            assert_eq!(n, 1);
+        });
+        rga.blit_fenced(&src, &mut dst, None, Some(&fence)).unwrap();
+        producer.join().unwrap();
+        assert_eq!(bo.as_bytes()[0], 0x5a);
+    }
+}
@@ -1,9 +1,15 @@
 use super::{
-    ffi, RgaBuffer, RgaColorSpaceMode, RgaInfo, RgaPixelFormat, RgaRect, RgaRop, RgaTransform,
+    ffi, Colorimetry, Dither, MemoryLayout, Normalize, RgaBuffer, RgaColorSpaceMode, RgaInfo,
+    RgaOrientation, RgaPixelFormat, RgaRect, RgaRop3, ScaleFilter,
 };
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::os::raw::c_void;
+#[cfg(feature = "v1_4_0")]
+use {
+    super::{CoreMask, Priority, RgaFence},
+    std::os::unix::io::AsRawFd,
+};
 
 /// 一个描述 RGA 图像区域构建器的类型。
 pub struct RgaRectBuilder {
@@ -11,6 +17,7 @@ pub struct RgaRectBuilder {
     size: Option<(i32, i32)>,
     stride: Option<(i32, i32)>,
     format: Option<RgaPixelFormat>,
+    layout: Option<MemoryLayout>,
 }
 
 impl RgaRectBuilder {
@@ -21,6 +28,7 @@ impl RgaRectBuilder {
             size: None,
             stride: None,
             format: None,
+            layout: None,
         }
     }
 
@@ -48,6 +56,14 @@ impl RgaRectBuilder {
         self
     }
 
+    /// 设置内存布局，未设置内部大小时按布局的对齐要求计算。
+    ///
+    /// 布局不保存在图像区域中，需同时通过 [`RgaInfoBuilder::layout`] 设置到操作信息上。
+    pub fn layout(mut self, layout: MemoryLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
     /// 生成适用于 RGA 的图像区域信息。
     pub fn build(self) -> RgaRect {
         let (w, h) = self
@@ -57,7 +73,10 @@ impl RgaRectBuilder {
             .format
             .expect("RgaRectBuilder: `format` could not be empty!");
         let (x, y) = self.offset.unwrap_or_default();
-        let (ws, hs) = self.stride.unwrap_or((w, h));
+        let (ax, ay) = self.layout.unwrap_or_default().alignment();
+        let (ws, hs) = self
+            .stride
+            .unwrap_or(((w + ax - 1) / ax * ax, (h + ay - 1) / ay * ay));
 
         RgaRect {
             xoffset: x,
@@ -87,8 +106,21 @@ pub struct RgaInfoBuilder<'a> {
     color: Option<i32>,
     format: Option<RgaPixelFormat>,
     color_space_mode: Option<RgaColorSpaceMode>,
-    rop: Option<RgaRop>,
-    rotation: Option<RgaTransform>,
+    colorimetry: Option<Colorimetry>,
+    rop: Option<RgaRop3>,
+    rotation: Option<RgaOrientation>,
+    scale_filter: Option<ScaleFilter>,
+    normalize: Option<Normalize>,
+    dither: Option<Dither>,
+    async_mode: bool,
+    #[cfg(feature = "v1_4_0")]
+    in_fence: Option<&'a RgaFence>,
+    #[cfg(feature = "v1_4_0")]
+    layout: Option<MemoryLayout>,
+    #[cfg(feature = "v1_4_0")]
+    core: Option<CoreMask>,
+    #[cfg(feature = "v1_4_0")]
+    priority: Option<Priority>,
 }
 
 impl<'a> RgaInfoBuilder<'a> {
@@ -102,8 +134,21 @@ impl<'a> RgaInfoBuilder<'a> {
             color: None,
             format: None,
             color_space_mode: None,
+            colorimetry: None,
             rop: None,
             rotation: None,
+            scale_filter: None,
+            normalize: None,
+            dither: None,
+            async_mode: false,
+            #[cfg(feature = "v1_4_0")]
+            in_fence: None,
+            #[cfg(feature = "v1_4_0")]
+            layout: None,
+            #[cfg(feature = "v1_4_0")]
+            core: None,
+            #[cfg(feature = "v1_4_0")]
+            priority: None,
         }
     }
 
@@ -149,19 +194,82 @@ impl<'a> RgaInfoBuilder<'a> {
         self
     }
 
+    /// 设置图像的色度信息，供 [`Rga::csc_auto`] 选择转换模式。
+    ///
+    /// [`Rga::csc_auto`]: super::Rga::csc_auto
+    pub fn colorimetry(mut self, colorimetry: Colorimetry) -> Self {
+        self.colorimetry = Some(colorimetry);
+        self
+    }
+
     /// 设置位操作模式。
-    pub fn rop(mut self, rop: RgaRop) -> Self {
-        self.rop = Some(rop);
+    pub fn rop<R: Into<RgaRop3>>(mut self, rop: R) -> Self {
+        self.rop = Some(rop.into());
         self
     }
 
     /// 设置旋转模式。
-    pub fn rotation(mut self, rotation: RgaTransform) -> Self {
-        self.rotation = Some(rotation);
+    pub fn rotation<T: Into<RgaOrientation>>(mut self, rotation: T) -> Self {
+        self.rotation = Some(rotation.into());
+        self
+    }
+
+    /// 设置缩放插值方式，应设置在源图像上。
+    pub fn scale_filter(mut self, filter: ScaleFilter) -> Self {
+        self.scale_filter = Some(filter);
+        self
+    }
+
+    /// 设置逐通道归一化参数，应设置在目标图像上。
+    pub fn normalize(mut self, normalize: Normalize) -> Self {
+        self.normalize = Some(normalize);
+        self
+    }
+
+    /// 设置降低色深时的有序抖动参数，应设置在目标图像上。
+    pub fn dither(mut self, dither: Dither) -> Self {
+        self.dither = Some(dither);
+        self
+    }
+
+    /// 使用异步模式（`RGA_BLIT_ASYNC`）提交，操作完成后可通过 `out_fence_fd` 获取栅栏。
+    pub fn async_mode(mut self, async_mode: bool) -> Self {
+        self.async_mode = async_mode;
+        self
+    }
+
+    /// 设置输入栅栏，硬件在其触发后才开始处理，应设置在目标图像上。
+    #[cfg(feature = "v1_4_0")]
+    pub fn in_fence(mut self, fence: &'a RgaFence) -> Self {
+        self.in_fence = Some(fence);
+        self
+    }
+
+    /// 设置图像的内存布局，执行时校验格式及对齐。
+    #[cfg(feature = "v1_4_0")]
+    pub fn layout(mut self, layout: MemoryLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// 设置可调度的核心，执行时按检测到的硬件校验。
+    #[cfg(feature = "v1_4_0")]
+    pub fn core(mut self, core: CoreMask) -> Self {
+        self.core = Some(core);
+        self
+    }
+
+    /// 设置任务优先级。
+    #[cfg(feature = "v1_4_0")]
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
         self
     }
 
     /// 构建 RGA 操作信息。
+    ///
+    /// # Panics
+    /// 当归一化参数超出硬件范围时触发，可事先使用 [`Normalize::to_raw`] 检查。
     pub fn build(self) -> RgaInfoRef<'a> {
         let mut info: RgaInfo = Default::default();
 
@@ -200,22 +308,52 @@ impl<'a> RgaInfoBuilder<'a> {
         }
 
         if let Some(rop) = self.rop {
-            info.rop_code = rop as i32;
+            info.rop_code = rop.code() as i32;
         }
 
         if let Some(rotation) = self.rotation {
-            info.rotation = rotation as i32;
+            info.rotation = rotation.to_raw();
+        }
+
+        if let Some(filter) = self.scale_filter {
+            info.scale_mode = filter as i32;
+        }
+
+        if let Some(normalize) = self.normalize {
+            info.nn = normalize
+                .to_raw()
+                .expect("RgaInfoBuilder: `normalize` out of range!");
+        }
+
+        if let Some(dither) = self.dither {
+            info.dither = dither.to_raw();
         }
 
         #[cfg(feature = "v1_4_0")]
         {
-            info.in_fence_fd = -1;
+            info.in_fence_fd = self.in_fence.map_or(-1, |f| f.as_raw_fd());
             info.out_fence_fd = -1;
+            if let Some(layout) = self.layout {
+                info.rd_mode = layout.rd_mode();
+            }
+            if let Some(core) = self.core {
+                info.core = core.bits() as i32;
+            }
+            if let Some(priority) = self.priority {
+                info.priority = priority.value() as i32;
+            }
         }
 
-        info.sync_mode = ffi::RGA_BLIT_SYNC as i32;
+        info.sync_mode = if self.async_mode {
+            ffi::RGA_BLIT_ASYNC
+        } else {
+            ffi::RGA_BLIT_SYNC
+        } as i32;
 
-        RgaInfoRef::new(info)
+        RgaInfoRef {
+            colorimetry: self.colorimetry,
+            ..RgaInfoRef::new(info)
+        }
     }
 }
 
@@ -228,6 +366,7 @@ impl<'a> Default for RgaInfoBuilder<'a> {
 /// 一个描述 RGA 操作信息引用的类型。
 pub struct RgaInfoRef<'a> {
     info: RgaInfo,
+    colorimetry: Option<Colorimetry>,
     _phantom: PhantomData<&'a ()>,
 }
 
@@ -236,9 +375,20 @@ impl<'a> RgaInfoRef<'a> {
     pub fn new(info: RgaInfo) -> Self {
         Self {
             info,
+            colorimetry: None,
             _phantom: PhantomData,
         }
     }
+
+    /// 返回图像的色度信息。
+    pub fn colorimetry(&self) -> Option<Colorimetry> {
+        self.colorimetry
+    }
+
+    /// 设置图像的色度信息。
+    pub fn set_colorimetry(&mut self, colorimetry: Option<Colorimetry>) {
+        self.colorimetry = colorimetry;
+    }
 }
 
 impl<'a> Deref for RgaInfoRef<'a> {
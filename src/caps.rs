@@ -0,0 +1,312 @@
+//! 硬件能力检测。
+//!
+use super::{CoreMask, Rga, ScaleFilter};
+use std::io;
+
+/// RGA 驱动输出硬件信息的位置。
+const HARDWARE_INFO_PATHS: &[&str] = &["/sys/kernel/debug/rkrga/hardware", "/proc/rkrga/hardware"];
+
+/// 一个描述 RGA 核心类型的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RgaCoreKind {
+    Rga1,
+    Rga2,
+    Rga3,
+}
+
+impl RgaCoreKind {
+    /// 当核心支持指定的插值方式时返回 true。
+    pub fn supports_filter(self, filter: ScaleFilter) -> bool {
+        match filter {
+            ScaleFilter::Bilinear => true,
+            ScaleFilter::Nearest => matches!(self, Self::Rga1 | Self::Rga2),
+            ScaleFilter::Bicubic => matches!(self, Self::Rga2),
+        }
+    }
+
+    /// 返回默认的最大输入宽高。
+    pub fn default_max_size(self) -> (i32, i32) {
+        match self {
+            Self::Rga1 => (2048, 2048),
+            Self::Rga2 => (8192, 8192),
+            Self::Rga3 => (8176, 8176),
+        }
+    }
+
+    /// 返回默认的最大输出宽高。
+    pub fn default_max_output_size(self) -> (i32, i32) {
+        match self {
+            Self::Rga1 => (2048, 2048),
+            Self::Rga2 => (4096, 4096),
+            Self::Rga3 => (8128, 8128),
+        }
+    }
+
+    /// 返回默认的最大缩小及放大倍数。
+    pub fn default_scale_limit(self) -> (u32, u32) {
+        match self {
+            Self::Rga1 => (8, 8),
+            Self::Rga2 => (16, 16),
+            Self::Rga3 => (8, 8),
+        }
+    }
+}
+
+/// 一个描述单个 RGA 核心的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RgaCoreInfo {
+    /// 核心类型。
+    pub kind: RgaCoreKind,
+    /// 调度器中的核心标识。
+    pub id: u32,
+    /// 最大输入宽高。
+    pub max_size: (i32, i32),
+    /// 最大输出宽高。
+    pub max_output_size: (i32, i32),
+    /// 最大缩小及放大倍数。
+    pub scale_limit: (u32, u32),
+}
+
+impl RgaCoreInfo {
+    /// 使用核心类型的默认参数创建。
+    pub fn new(kind: RgaCoreKind, id: u32) -> Self {
+        Self {
+            kind,
+            id,
+            max_size: kind.default_max_size(),
+            max_output_size: kind.default_max_output_size(),
+            scale_limit: kind.default_scale_limit(),
+        }
+    }
+}
+
+/// 一个描述 RGA 硬件能力的类型。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RgaCapabilities {
+    /// 检测到的核心。
+    pub cores: Vec<RgaCoreInfo>,
+}
+
+impl RgaCapabilities {
+    /// 从驱动输出的硬件信息中检测，无法读取时返回 [`Default`] 值。
+    pub fn detect() -> Self {
+        HARDWARE_INFO_PATHS
+            .iter()
+            .filter_map(|path| std::fs::read_to_string(path).ok())
+            .map(|text| Self::parse(&text))
+            .find(|caps| !caps.cores.is_empty())
+            .unwrap_or_default()
+    }
+
+    /// 解析驱动输出的硬件信息，格式如下：
+    ///
+    /// ```text
+    /// rga3_core0, core 1: version: 3.0.76831
+    /// input range: 68x2 ~ 8176x8176
+    /// output range: 68x2 ~ 8128x8128
+    /// scale limit: 1/8 ~ 8
+    /// ```
+    pub fn parse(text: &str) -> Self {
+        let mut cores: Vec<RgaCoreInfo> = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if let Some((name, rest)) = line.split_once(", core ") {
+                let kind = if name.starts_with("rga3") {
+                    RgaCoreKind::Rga3
+                } else if name.starts_with("rga2") {
+                    RgaCoreKind::Rga2
+                } else {
+                    RgaCoreKind::Rga1
+                };
+                let id = rest
+                    .split(':')
+                    .next()
+                    .and_then(|s| s.trim().parse().ok())
+                    .unwrap_or(0);
+                cores.push(RgaCoreInfo::new(kind, id));
+            } else if let (Some(core), Some(range)) =
+                (cores.last_mut(), line.strip_prefix("input range:"))
+            {
+                if let Some(size) = range.split('~').nth(1).and_then(parse_size) {
+                    core.max_size = size;
+                }
+            } else if let (Some(core), Some(range)) =
+                (cores.last_mut(), line.strip_prefix("output range:"))
+            {
+                if let Some(size) = range.split('~').nth(1).and_then(parse_size) {
+                    core.max_output_size = size;
+                }
+            } else if let (Some(core), Some(limit)) =
+                (cores.last_mut(), line.strip_prefix("scale limit:"))
+            {
+                let mut parts = limit.split('~').map(str::trim);
+                let down = parts
+                    .next()
+                    .and_then(|s| s.strip_prefix("1/")?.parse().ok());
+                let up = parts.next().and_then(|s| s.parse().ok());
+                if let (Some(down), Some(up)) = (down, up) {
+                    core.scale_limit = (down, up);
+                }
+            }
+        }
+        Self { cores }
+    }
+
+    /// 当任一核心支持指定的插值方式时返回 true。
+    pub fn supports_filter(&self, filter: ScaleFilter) -> bool {
+        self.supports_filter_on(filter, CoreMask::AUTO)
+    }
+
+    /// 当 `mask` 中任一核心支持指定的插值方式时返回 true，`mask` 为空时检查所有核心。
+    ///
+    /// 硬件能力未知时总是返回 true，交由驱动判断。
+    pub fn supports_filter_on(&self, filter: ScaleFilter, mask: CoreMask) -> bool {
+        self.is_unknown()
+            || self
+                .cores
+                .iter()
+                .filter(|c| mask.is_auto() || mask.contains(CoreMask::from_bits(c.id)))
+                .any(|c| c.kind.supports_filter(filter))
+    }
+
+    /// 当未检测到任何核心、硬件能力未知时返回 true。
+    ///
+    /// 此时各项限制均不生效，`max_size_on` 等返回最大值。
+    pub fn is_unknown(&self) -> bool {
+        self.cores.is_empty()
+    }
+
+    /// 返回 `mask` 中所有核心均支持的最大输入宽高，`mask` 为空时考虑所有核心。
+    pub fn max_size_on(&self, mask: CoreMask) -> (i32, i32) {
+        self.cores
+            .iter()
+            .filter(|c| mask.is_auto() || mask.contains(CoreMask::from_bits(c.id)))
+            .fold((i32::MAX, i32::MAX), |(w, h), c| {
+                (w.min(c.max_size.0), h.min(c.max_size.1))
+            })
+    }
+
+    /// 返回 `mask` 中所有核心均支持的最大输出宽高，`mask` 为空时考虑所有核心。
+    pub fn max_output_size_on(&self, mask: CoreMask) -> (i32, i32) {
+        self.cores
+            .iter()
+            .filter(|c| mask.is_auto() || mask.contains(CoreMask::from_bits(c.id)))
+            .fold((i32::MAX, i32::MAX), |(w, h), c| {
+                (w.min(c.max_output_size.0), h.min(c.max_output_size.1))
+            })
+    }
+
+    /// 返回 `mask` 中所有核心均支持的最大缩小及放大倍数，`mask` 为空时考虑所有核心。
+    pub fn scale_limit_on(&self, mask: CoreMask) -> (u32, u32) {
+        self.cores
+            .iter()
+            .filter(|c| mask.is_auto() || mask.contains(CoreMask::from_bits(c.id)))
+            .fold((u32::MAX, u32::MAX), |(down, up), c| {
+                (down.min(c.scale_limit.0), up.min(c.scale_limit.1))
+            })
+    }
+
+    /// 返回所有检测到的核心组成的集合。
+    pub fn core_mask(&self) -> CoreMask {
+        self.cores
+            .iter()
+            .fold(CoreMask::AUTO, |m, c| m | CoreMask::from_bits(c.id))
+    }
+}
+
+impl Default for RgaCapabilities {
+    /// 无法检测时核心列表为空，表示硬件能力未知。
+    fn default() -> Self {
+        Self { cores: Vec::new() }
+    }
+}
+
+fn parse_size(s: &str) -> Option<(i32, i32)> {
+    let (w, h) = s.trim().split_once('x')?;
+    Some((w.trim().parse().ok()?, h.trim().parse().ok()?))
+}
+
+impl Rga {
+    /// 返回 RGA 硬件能力，首次调用时检测。
+    pub fn capabilities(&self) -> &RgaCapabilities {
+        self.caps.get_or_init(RgaCapabilities::detect)
+    }
+
+    /// 检查 `mask` 中的核心是否支持指定的插值方式。
+    pub(crate) fn check_filter(
+        &self,
+        filter: ScaleFilter,
+        mask: CoreMask,
+    ) -> Result<(), io::Error> {
+        if self.capabilities().supports_filter_on(filter, mask) {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("scale filter {:?} is not supported by the hardware", filter),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RK3588: &str = "\
+===================================
+rga3_core0, core 1: version: 3.0.76831
+input range: 68x2 ~ 8176x8176
+output range: 68x2 ~ 8128x8128
+scale limit: 1/8 ~ 8
+-----------------------------------
+rga3_core1, core 2: version: 3.0.76831
+input range: 68x2 ~ 8176x8176
+output range: 68x2 ~ 8128x8128
+scale limit: 1/8 ~ 8
+-----------------------------------
+rga2, core 4: version: 3.2.63318
+input range: 2x2 ~ 8192x8192
+output range: 2x2 ~ 4096x4096
+scale limit: 1/16 ~ 16
+";
+
+    #[test]
+    fn test_parse_hardware_info() {
+        let caps = RgaCapabilities::parse(RK3588);
+        assert_eq!(caps.cores.len(), 3);
+        assert_eq!(caps.cores[0].kind, RgaCoreKind::Rga3);
+        assert_eq!(caps.cores[1].id, 2);
+        assert_eq!(caps.cores[2].kind, RgaCoreKind::Rga2);
+        assert_eq!(caps.cores[2].id, 4);
+        assert_eq!(caps.cores[2].max_size, (8192, 8192));
+        assert_eq!(caps.cores[2].scale_limit, (16, 16));
+        assert_eq!(caps.cores[0].scale_limit, (8, 8));
+        assert_eq!(caps.max_size_on(CoreMask::AUTO), (8176, 8176));
+        assert_eq!(caps.max_size_on(CoreMask::RGA2_CORE0), (8192, 8192));
+        assert_eq!(caps.cores[2].max_output_size, (4096, 4096));
+        assert_eq!(caps.max_output_size_on(CoreMask::RGA3), (8128, 8128));
+        assert_eq!(caps.max_output_size_on(CoreMask::AUTO), (4096, 4096));
+        assert_eq!(caps.scale_limit_on(CoreMask::AUTO), (8, 8));
+        assert_eq!(caps.scale_limit_on(CoreMask::RGA2_CORE0), (16, 16));
+        assert!(RgaCapabilities::parse("").is_unknown());
+        let unknown = RgaCapabilities::default();
+        assert!(unknown.is_unknown());
+        assert!(unknown.supports_filter_on(ScaleFilter::Bicubic, CoreMask::RGA3));
+        assert_eq!(unknown.max_size_on(CoreMask::AUTO), (i32::MAX, i32::MAX));
+    }
+
+    #[test]
+    fn test_filter_support() {
+        let rga3 = RgaCapabilities {
+            cores: vec![RgaCoreInfo::new(RgaCoreKind::Rga3, 1)],
+        };
+        assert!(rga3.supports_filter(ScaleFilter::Bilinear));
+        assert!(!rga3.supports_filter(ScaleFilter::Nearest));
+        assert!(!rga3.supports_filter(ScaleFilter::Bicubic));
+        let all = RgaCapabilities::parse(RK3588);
+        assert!(all.supports_filter(ScaleFilter::Bicubic));
+        assert!(!all.supports_filter_on(ScaleFilter::Bicubic, CoreMask::RGA3));
+        assert!(all.supports_filter_on(ScaleFilter::Nearest, CoreMask::RGA2_CORE0));
+        assert_eq!(all.core_mask(), CoreMask::RGA3 | CoreMask::RGA2_CORE0);
+    }
+}
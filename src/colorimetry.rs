@@ -0,0 +1,138 @@
+//! 图像色度信息及颜色空间转换模式的自动选择。
+//!
+use super::{
+    ColorMatrix, ColorRange, ColorStandard, Rga, RgaColorSpaceMode, RgaInfo, RgaInfoRef,
+    RgaPixelFormat,
+};
+use std::io;
+
+/// 一个描述 YUV 图像色度信息的类型，RGB 图像总是视为全范围。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Colorimetry {
+    pub standard: ColorStandard,
+    pub range: ColorRange,
+}
+
+impl Colorimetry {
+    /// BT.601 有限范围，未指定色度信息时的默认值。
+    pub const BT601_LIMITED: Self = Self::new(ColorStandard::Bt601, ColorRange::Limited);
+    /// BT.601 全范围（JPEG）。
+    pub const BT601_FULL: Self = Self::new(ColorStandard::Bt601, ColorRange::Full);
+    /// BT.709 有限范围。
+    pub const BT709_LIMITED: Self = Self::new(ColorStandard::Bt709, ColorRange::Limited);
+    /// BT.709 全范围。
+    pub const BT709_FULL: Self = Self::new(ColorStandard::Bt709, ColorRange::Full);
+    /// BT.2020 有限范围。
+    pub const BT2020_LIMITED: Self = Self::new(ColorStandard::Bt2020, ColorRange::Limited);
+    /// BT.2020 全范围。
+    pub const BT2020_FULL: Self = Self::new(ColorStandard::Bt2020, ColorRange::Full);
+
+    /// 创建色度信息。
+    pub const fn new(standard: ColorStandard, range: ColorRange) -> Self {
+        Self { standard, range }
+    }
+
+    /// 返回 `src` 转换到 `dst` 所需的转换矩阵，无需转换时返回 None。
+    pub fn matrix(
+        src_fmt: RgaPixelFormat,
+        src: Self,
+        dst_fmt: RgaPixelFormat,
+        dst: Self,
+    ) -> Option<ColorMatrix> {
+        match (src_fmt.is_yuv(), dst_fmt.is_yuv()) {
+            (false, false) => None,
+            (true, false) => Some(ColorMatrix::yuv_to_rgb(src.standard, src.range)),
+            (false, true) => Some(ColorMatrix::rgb_to_yuv(dst.standard, dst.range)),
+            (true, true) if src == dst => None,
+            (true, true) => Some(ColorMatrix::yuv_to_yuv(
+                (src.standard, src.range),
+                (dst.standard, dst.range),
+            )),
+        }
+    }
+
+    /// 选择 `src` 转换到 `dst` 时使用的颜色空间转换模式，无需转换时返回 None，
+    /// 硬件不支持此转换时返回 `Unsupported` 错误。
+    pub fn csc_mode(
+        src_fmt: RgaPixelFormat,
+        src: Self,
+        dst_fmt: RgaPixelFormat,
+        dst: Self,
+    ) -> Result<Option<RgaColorSpaceMode>, io::Error> {
+        let Some(matrix) = Self::matrix(src_fmt, src, dst_fmt, dst) else {
+            return Ok(None);
+        };
+        match matrix.preset() {
+            Some(mode) => Ok(Some(mode)),
+            None => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "conversion from {:?} {:?} to {:?} {:?} is not supported by hardware",
+                    src_fmt, src, dst_fmt, dst
+                ),
+            )),
+        }
+    }
+}
+
+impl Rga {
+    /// 按图像的色度信息自动选择转换模式，对象图像进行色彩空间转换操作。
+    ///
+    /// 未设置色度信息的图像视为 [`Colorimetry::BT601_LIMITED`]。
+    pub fn csc_auto(&self, src: &RgaInfoRef, dst: &mut RgaInfoRef) -> Result<(), io::Error> {
+        let mode = Colorimetry::csc_mode(
+            RgaPixelFormat::from_raw(src.format),
+            src.colorimetry().unwrap_or_default(),
+            RgaPixelFormat::from_raw(dst.format),
+            dst.colorimetry().unwrap_or_default(),
+        )?;
+        let src = RgaInfo {
+            color_space_mode: mode.map_or(0, |m| m as i32),
+            ..**src
+        };
+        self.csc(&src, dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csc_mode_selection() {
+        use RgaPixelFormat::*;
+        let mode = |s, sc, d, dc| Colorimetry::csc_mode(s, sc, d, dc).map(|m| m.map(|m| m as i32));
+        let bt601 = Colorimetry::BT601_LIMITED;
+        let bt709 = Colorimetry::BT709_LIMITED;
+        let jpeg = Colorimetry::BT601_FULL;
+        assert_eq!(mode(Rgba8888, bt709, Rgb888, jpeg).unwrap(), None);
+        assert_eq!(mode(YCbCr420sp, bt709, Rgba8888, bt601).unwrap(), Some(0x2));
+        assert_eq!(mode(YCbCr420sp, jpeg, Rgba8888, bt709).unwrap(), Some(0x1));
+        assert_eq!(
+            mode(Rgba8888, bt601, YCbCr420sp, jpeg).unwrap(),
+            Some(0x100)
+        );
+        // 未设置色度信息时的默认转换使用模式 0。
+        assert_eq!(
+            mode(
+                Rgba8888,
+                Colorimetry::default(),
+                YCbCr420sp,
+                Colorimetry::default()
+            )
+            .unwrap(),
+            Some(0x0)
+        );
+        assert_eq!(mode(Rgb565, bt709, YCrCb420p, bt601).unwrap(), Some(0x0));
+        assert_eq!(mode(YCbCr420sp, bt601, YCrCb420sp, bt601).unwrap(), None);
+        assert_eq!(
+            mode(YCbCr420sp, jpeg, YCbCr420sp, bt709).unwrap(),
+            Some(0x700)
+        );
+
+        // 硬件不支持 BT.2020 及 BT.709 全范围到 RGB 的转换。
+        let err = mode(YCbCr420sp, Colorimetry::BT2020_LIMITED, Rgba8888, bt601).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert!(mode(YCbCr420sp, Colorimetry::BT709_FULL, Rgba8888, bt601).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! 裁剪及平移操作。
+//!
+use super::geom::{bounds_of, invalid_input, with_region};
+use super::{Rect, Rga, RgaAlignPolicy, RgaInfo, RgaPixelFormat};
+use std::io;
+
+impl Rga {
+    /// 将源图像的 `src_region` 区域复制（必要时缩放）到目标图像。
+    ///
+    /// `src_region` 以源图像区域左上角为原点，超出图像的部分会被裁掉；
+    /// YUV 格式下未按色度采样对齐的区域按 [`align_policy`] 处理。
+    ///
+    /// [`align_policy`]: Rga::align_policy()
+    pub fn crop(
+        &self,
+        src: &RgaInfo,
+        src_region: Rect,
+        dst: &mut RgaInfo,
+    ) -> Result<(), io::Error> {
+        bounds_of(dst)?;
+        let region = self.crop_region(src, src_region)?;
+        let src = with_region(src, &region);
+        self.blit(&src, dst, None)
+    }
+
+    /// 返回裁剪到源图像内并按对齐策略处理后的区域。
+    fn crop_region(&self, src: &RgaInfo, src_region: Rect) -> Result<Rect, io::Error> {
+        let src_bounds = bounds_of(src)?;
+        let region = src_region
+            .intersect(&src_bounds)
+            .ok_or_else(|| invalid_input("crop region is outside of the source image"))?;
+        self.align_policy().apply(
+            region,
+            RgaPixelFormat::from_raw(src.rect.format),
+            &src_bounds,
+        )
+    }
+
+    /// 将源图像平移 `(dx, dy)` 后复制到目标图像。
+    ///
+    /// 超出目标图像的部分会被裁掉，完全位于目标图像之外时不执行任何操作；
+    /// YUV 格式下未按色度采样对齐的偏移按 [`align_policy`] 处理。
+    ///
+    /// [`align_policy`]: Rga::align_policy()
+    pub fn translate(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        dx: i32,
+        dy: i32,
+    ) -> Result<(), io::Error> {
+        let (src_region, dst_region) = match self.translate_regions(src, dst, dx, dy)? {
+            Some(regions) => regions,
+            None => return Ok(()),
+        };
+        let src = with_region(src, &src_region);
+        let mut sub = with_region(dst, &dst_region);
+        self.blit(&src, &mut sub, None)
+    }
+
+    /// 返回平移后源、目标图像中对应的区域，完全位于目标图像之外时返回 None。
+    fn translate_regions(
+        &self,
+        src: &RgaInfo,
+        dst: &RgaInfo,
+        dx: i32,
+        dy: i32,
+    ) -> Result<Option<(Rect, Rect)>, io::Error> {
+        let src_bounds = bounds_of(src)?;
+        let dst_bounds = bounds_of(dst)?;
+        let (sx, sy) = RgaPixelFormat::from_raw(src.rect.format)
+            .chroma_subsampling()
+            .unwrap_or((1, 1));
+        let (tx, ty) = RgaPixelFormat::from_raw(dst.rect.format)
+            .chroma_subsampling()
+            .unwrap_or((1, 1));
+        let (ax, ay) = (sx.max(tx), sy.max(ty));
+        let (dx, dy) = if dx % ax == 0 && dy % ay == 0 {
+            (dx, dy)
+        } else {
+            match self.align_policy() {
+                RgaAlignPolicy::Round => (dx.div_euclid(ax) * ax, dy.div_euclid(ay) * ay),
+                RgaAlignPolicy::Error => {
+                    return Err(invalid_input(
+                        "translate offset is not aligned to chroma subsampling",
+                    ))
+                }
+            }
+        };
+        Ok(src_bounds
+            .translate(dx, dy)
+            .intersect(&dst_bounds)
+            .map(|r| (r.translate(-dx, -dy), r)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RgaRectBuilder;
+
+    fn info(width: i32, height: i32, format: RgaPixelFormat) -> RgaInfo {
+        let rect = RgaRectBuilder::new()
+            .size(width, height)
+            .format(format)
+            .build();
+        RgaInfo {
+            rect,
+            format: rect.format,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_crop_region() {
+        let rga = Rga::new().unwrap();
+        let rgba = info(64, 32, RgaPixelFormat::Rgba8888);
+        // 负偏移及超出图像的部分被裁掉。
+        assert_eq!(
+            rga.crop_region(&rgba, Rect::new(-8, -4, 20, 10)).unwrap(),
+            Rect::new(0, 0, 12, 6)
+        );
+        assert_eq!(
+            rga.crop_region(&rgba, Rect::new(60, 30, 10, 10)).unwrap(),
+            Rect::new(60, 30, 4, 2)
+        );
+        let err = rga.crop_region(&rgba, Rect::new(64, 0, 4, 4)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // YUV 区域起点向下、终点向上对齐到偶数，且不超出图像。
+        let nv12 = info(64, 32, RgaPixelFormat::YCbCr420sp);
+        assert_eq!(
+            rga.crop_region(&nv12, Rect::new(3, 5, 9, 7)).unwrap(),
+            Rect::new(2, 4, 10, 8)
+        );
+        assert_eq!(
+            rga.crop_region(&nv12, Rect::new(-3, 29, 6, 9)).unwrap(),
+            Rect::new(0, 28, 4, 4)
+        );
+        rga.set_align_policy(RgaAlignPolicy::Error);
+        assert!(rga.crop_region(&nv12, Rect::new(3, 5, 9, 7)).is_err());
+        assert!(rga.crop_region(&nv12, Rect::new(2, 4, 10, 8)).is_ok());
+    }
+
+    #[test]
+    fn test_translate_regions() {
+        let rga = Rga::new().unwrap();
+        let src = info(16, 8, RgaPixelFormat::Rgba8888);
+        let dst = info(32, 16, RgaPixelFormat::Rgba8888);
+        assert_eq!(
+            rga.translate_regions(&src, &dst, -4, -2).unwrap(),
+            Some((Rect::new(4, 2, 12, 6), Rect::new(0, 0, 12, 6)))
+        );
+        assert_eq!(
+            rga.translate_regions(&src, &dst, 20, 12).unwrap(),
+            Some((Rect::new(0, 0, 12, 4), Rect::new(20, 12, 12, 4)))
+        );
+        assert_eq!(rga.translate_regions(&src, &dst, 32, 0).unwrap(), None);
+        assert_eq!(rga.translate_regions(&src, &dst, -16, 0).unwrap(), None);
+
+        // 偏移按色度采样向下对齐：-3 -> -4，5 -> 4。
+        let nv12 = info(16, 8, RgaPixelFormat::YCbCr420sp);
+        assert_eq!(
+            rga.translate_regions(&nv12, &dst, -3, 5).unwrap(),
+            Some((Rect::new(4, 0, 12, 8), Rect::new(0, 4, 12, 8)))
+        );
+        rga.set_align_policy(RgaAlignPolicy::Error);
+        assert!(rga.translate_regions(&nv12, &dst, -3, 5).is_err());
+        assert!(rga.translate_regions(&src, &dst, -3, 5).is_ok());
+    }
+}
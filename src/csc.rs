@@ -0,0 +1,404 @@
+//! 自定义颜色空间转换矩阵。
+//!
+//! 硬件以 `full_csc` 参数支持任意 3x3 矩阵加偏移的转换，但该参数仅存在于内核接口的
+//! `rga_req` 中（librga 1.2.3 ~ 1.7.2），任何版本的 `rga_info` 均不包含该参数，
+//! 因此 [`Rga::csc_with`] 只能将矩阵映射到内置的 [`RgaColorSpaceMode`] 预设，
+//! 不支持自定义矩阵，无法映射时返回 `Unsupported` 错误，
+//! 可改用 [`ColorMatrix::apply`] 在 CPU 上完成转换。
+use super::geom::invalid_input;
+use super::{Rga, RgaColorSpaceMode, RgaInfo};
+use std::io;
+
+/// 定点系数的小数位数。
+pub const CSC_FRAC_BITS: u32 = 10;
+
+/// 一个描述颜色标准的枚举，取值决定亮度系数 `Kr`、`Kb`。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorStandard {
+    /// ITU-R BT.601。
+    #[default]
+    Bt601,
+    /// ITU-R BT.709。
+    Bt709,
+    /// ITU-R BT.2020（非恒定亮度）。
+    Bt2020,
+}
+
+impl ColorStandard {
+    /// 返回 `(Kr, Kb)`。
+    pub fn luma_coefficients(self) -> (f32, f32) {
+        match self {
+            Self::Bt601 => (0.299, 0.114),
+            Self::Bt709 => (0.2126, 0.0722),
+            Self::Bt2020 => (0.2627, 0.0593),
+        }
+    }
+}
+
+/// 一个描述 YUV 取值范围的枚举。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorRange {
+    /// 全范围（JPEG），Y、U、V 取值 0 ~ 255。
+    Full,
+    /// 有限范围（MPEG），Y 取值 16 ~ 235，U、V 取值 16 ~ 240。
+    #[default]
+    Limited,
+}
+
+impl ColorRange {
+    /// 返回 `(Y 偏移, Y 幅度, UV 幅度)`。
+    fn scales(self) -> (f32, f32, f32) {
+        match self {
+            Self::Full => (0.0, 255.0, 255.0),
+            Self::Limited => (16.0, 219.0, 224.0),
+        }
+    }
+}
+
+/// 一个描述颜色模型的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ColorModel {
+    /// 按 R、G、B 排列。
+    Rgb,
+    /// 按 Y、U、V 排列。
+    Yuv,
+}
+
+/// 一个描述颜色空间转换矩阵的类型。
+///
+/// 分量取值均为 0 ~ 255，按 `out[i] = sum(coeffs[i][j] * in[j]) + offset[i]` 计算，
+/// 输入、输出分量分别按 `input`、`output` 的模型排列。
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ColorMatrix {
+    pub input: ColorModel,
+    pub output: ColorModel,
+    pub coeffs: [[f32; 3]; 3],
+    pub offset: [f32; 3],
+}
+
+impl ColorMatrix {
+    /// 创建一个转换矩阵。
+    pub const fn new(
+        input: ColorModel,
+        output: ColorModel,
+        coeffs: [[f32; 3]; 3],
+        offset: [f32; 3],
+    ) -> Self {
+        Self {
+            input,
+            output,
+            coeffs,
+            offset,
+        }
+    }
+
+    /// 创建 RGB -> YUV 的转换矩阵。
+    pub fn rgb_to_yuv(standard: ColorStandard, range: ColorRange) -> Self {
+        let (kr, kb) = standard.luma_coefficients();
+        let kg = 1.0 - kr - kb;
+        let (oy, sy, sc) = range.scales();
+        let (sy, sc) = (sy / 255.0, sc / 255.0);
+        let cb = 2.0 * (1.0 - kb);
+        let cr = 2.0 * (1.0 - kr);
+        Self::new(
+            ColorModel::Rgb,
+            ColorModel::Yuv,
+            [
+                [sy * kr, sy * kg, sy * kb],
+                [-sc * kr / cb, -sc * kg / cb, sc * (1.0 - kb) / cb],
+                [sc * (1.0 - kr) / cr, -sc * kg / cr, -sc * kb / cr],
+            ],
+            [oy, 128.0, 128.0],
+        )
+    }
+
+    /// 创建 YUV -> RGB 的转换矩阵。
+    pub fn yuv_to_rgb(standard: ColorStandard, range: ColorRange) -> Self {
+        Self::rgb_to_yuv(standard, range)
+            .inverse()
+            .expect("ColorMatrix: standard matrix is invertible")
+    }
+
+    /// 创建 YUV -> YUV 的转换矩阵。
+    pub fn yuv_to_yuv(from: (ColorStandard, ColorRange), to: (ColorStandard, ColorRange)) -> Self {
+        Self::yuv_to_rgb(from.0, from.1).then(&Self::rgb_to_yuv(to.0, to.1))
+    }
+
+    /// 返回先执行此转换、再执行 `next` 的组合转换。
+    pub fn then(&self, next: &Self) -> Self {
+        let mut coeffs = [[0.0; 3]; 3];
+        let mut offset = next.offset;
+        for (i, row) in coeffs.iter_mut().enumerate() {
+            for k in 0..3 {
+                let c = next.coeffs[i][k];
+                for (v, m) in row.iter_mut().zip(&self.coeffs[k]) {
+                    *v += c * m;
+                }
+                offset[i] += c * self.offset[k];
+            }
+        }
+        Self::new(self.input, next.output, coeffs, offset)
+    }
+
+    /// 返回逆转换，矩阵不可逆时返回 None。
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.coeffs;
+        let cof = |r0: usize, r1: usize, c0: usize, c1: usize| {
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+        let adj = [
+            [cof(1, 2, 1, 2), -cof(0, 2, 1, 2), cof(0, 1, 1, 2)],
+            [-cof(1, 2, 0, 2), cof(0, 2, 0, 2), -cof(0, 1, 0, 2)],
+            [cof(1, 2, 0, 1), -cof(0, 2, 0, 1), cof(0, 1, 0, 1)],
+        ];
+        let det = m[0][0] * adj[0][0] + m[0][1] * adj[1][0] + m[0][2] * adj[2][0];
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let mut coeffs = [[0.0; 3]; 3];
+        let mut offset = [0.0; 3];
+        for i in 0..3 {
+            for j in 0..3 {
+                coeffs[i][j] = adj[i][j] / det;
+                offset[i] -= coeffs[i][j] * self.offset[j];
+            }
+        }
+        Some(Self::new(self.output, self.input, coeffs, offset))
+    }
+
+    /// 返回与此矩阵一致的内置预设，不存在时返回 None。
+    ///
+    /// 模式 0 按源、目标格式决定方向，RGB 到 YUV 时为 BT.601 有限范围，
+    /// 因此 RGB 到 BT.601 有限范围 YUV 的矩阵同样返回 [`RgaColorSpaceMode::Yuv2RgbMode0`]。
+    pub fn preset(&self) -> Option<RgaColorSpaceMode> {
+        use ColorRange::*;
+        use ColorStandard::*;
+        use RgaColorSpaceMode::*;
+        let presets = [
+            (Yuv2RgbMode0, Self::yuv_to_rgb(Bt601, Limited)),
+            (Yuv2RgbMode0, Self::rgb_to_yuv(Bt601, Limited)),
+            (Yuv2RgbMode1, Self::yuv_to_rgb(Bt601, Full)),
+            (Yuv2RgbMode2, Self::yuv_to_rgb(Bt709, Limited)),
+            (RgbToYuv601Full, Self::rgb_to_yuv(Bt601, Full)),
+            (RgbToYuv709Full, Self::rgb_to_yuv(Bt709, Full)),
+            (
+                Yuv2Yuv601Limit709Limit,
+                Self::yuv_to_yuv((Bt601, Limited), (Bt709, Limited)),
+            ),
+            (
+                Yuv2Yuv601Limit709Full,
+                Self::yuv_to_yuv((Bt601, Limited), (Bt709, Full)),
+            ),
+            (
+                Yuv2Yuv709Limit601Limit,
+                Self::yuv_to_yuv((Bt709, Limited), (Bt601, Limited)),
+            ),
+            (
+                Yuv2Yuv709Limit601Full,
+                Self::yuv_to_yuv((Bt709, Limited), (Bt601, Full)),
+            ),
+            (
+                Yuv2Yuv601Full709Limit,
+                Self::yuv_to_yuv((Bt601, Full), (Bt709, Limited)),
+            ),
+            (
+                Yuv2Yuv601Full709Full,
+                Self::yuv_to_yuv((Bt601, Full), (Bt709, Full)),
+            ),
+            (
+                Yuv2Yuv709Full601Limit,
+                Self::yuv_to_yuv((Bt709, Full), (Bt601, Limited)),
+            ),
+            (
+                Yuv2Yuv709Full601Full,
+                Self::yuv_to_yuv((Bt709, Full), (Bt601, Full)),
+            ),
+        ];
+        presets
+            .into_iter()
+            .find(|(_, m)| self.approx_eq(m))
+            .map(|(mode, _)| mode)
+    }
+
+    fn approx_eq(&self, other: &Self) -> bool {
+        let close =
+            |a: &[f32], b: &[f32], eps: f32| a.iter().zip(b).all(|(a, b)| (a - b).abs() < eps);
+        self.input == other.input
+            && self.output == other.output
+            && self
+                .coeffs
+                .iter()
+                .zip(&other.coeffs)
+                .all(|(a, b)| close(a, b, 1e-4))
+            && close(&self.offset, &other.offset, 1e-2)
+    }
+
+    /// 返回 `CSC_FRAC_BITS` 位小数的定点系数及偏移，系数超出 16 位范围时截断。
+    fn quantize(&self) -> ([[i16; 3]; 3], [i32; 3]) {
+        let one = (1 << CSC_FRAC_BITS) as f32;
+        let mut coe = [[0i16; 3]; 3];
+        let mut off = [0i32; 3];
+        for (i, row) in coe.iter_mut().enumerate() {
+            for (c, v) in row.iter_mut().zip(&self.coeffs[i]) {
+                *c = (v * one).round() as i16;
+            }
+            off[i] = (self.offset[i] * one).round() as i32;
+        }
+        (coe, off)
+    }
+
+    /// 转换为定点形式，系数为 `CSC_FRAC_BITS` 位小数的 16 位整数，偏移同样按定点表示。
+    pub fn to_fixed(&self) -> Result<([[i16; 3]; 3], [i32; 3]), io::Error> {
+        let limit = (i16::MAX as f32 + 0.5) / (1 << CSC_FRAC_BITS) as f32;
+        if self.coeffs.iter().flatten().any(|c| c.abs() >= limit) {
+            return Err(invalid_input("ColorMatrix: coefficient out of range"));
+        }
+        Ok(self.quantize())
+    }
+
+    /// 转换为内核接口 `rga_req` 的 `full_csc` 参数，输入分量按 R/V、G/Y、B/U 的顺序排列。
+    #[cfg(all(feature = "v1_2_3", not(feature = "v1_8_0")))]
+    pub fn to_raw(&self) -> Result<super::ffi::full_csc_t, io::Error> {
+        let (coe, off) = self.to_fixed()?;
+        // YUV 输入时硬件按 V、Y、U 排列。
+        let [a, b, c] = match self.input {
+            ColorModel::Rgb => [0, 1, 2],
+            ColorModel::Yuv => [2, 0, 1],
+        };
+        let row = |i: usize| super::ffi::csc_coe_t {
+            r_v: coe[i][a],
+            g_y: coe[i][b],
+            b_u: coe[i][c],
+            off: off[i],
+        };
+        Ok(super::ffi::full_csc_t {
+            flag: 1,
+            coe_y: row(0),
+            coe_u: row(1),
+            coe_v: row(2),
+        })
+    }
+
+    /// 以硬件定点精度转换一个像素，作为软件参考实现。
+    pub fn apply(&self, px: [u8; 3]) -> [u8; 3] {
+        let (coe, off) = self.quantize();
+        let half = 1i32 << (CSC_FRAC_BITS - 1);
+        let mut out = [0u8; 3];
+        for i in 0..3 {
+            let mut sum = off[i] + half;
+            for j in 0..3 {
+                sum += coe[i][j] as i32 * px[j] as i32;
+            }
+            out[i] = (sum >> CSC_FRAC_BITS).clamp(0, 255) as u8;
+        }
+        out
+    }
+}
+
+impl Rga {
+    /// 使用指定矩阵对象图像进行色彩空间转换操作。
+    ///
+    /// 矩阵须与某个内置预设一致，否则返回 `Unsupported` 错误。
+    pub fn csc_with(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        matrix: &ColorMatrix,
+    ) -> Result<(), io::Error> {
+        let mode = matrix.preset().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "custom color matrix is not supported by librga, use ColorMatrix::apply instead",
+            )
+        })?;
+        let src = RgaInfo {
+            color_space_mode: mode as i32,
+            ..*src
+        };
+        self.csc(&src, dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn max_round_trip_error(standard: ColorStandard, range: ColorRange) -> i32 {
+        let fwd = ColorMatrix::rgb_to_yuv(standard, range);
+        let inv = ColorMatrix::yuv_to_rgb(standard, range);
+        let mut max = 0;
+        for r in (0..=255).step_by(15) {
+            for g in (0..=255).step_by(15) {
+                for b in (0..=255).step_by(15) {
+                    let rgb = [r as u8, g as u8, b as u8];
+                    let out = inv.apply(fwd.apply(rgb));
+                    for i in 0..3 {
+                        max = max.max((out[i] as i32 - rgb[i] as i32).abs());
+                    }
+                }
+            }
+        }
+        max
+    }
+
+    #[test]
+    fn test_color_matrix_round_trip() {
+        use ColorRange::*;
+        use ColorStandard::*;
+        for standard in [Bt601, Bt709, Bt2020] {
+            assert!(max_round_trip_error(standard, Full) <= 2);
+            assert!(max_round_trip_error(standard, Limited) <= 3);
+        }
+        let m = ColorMatrix::rgb_to_yuv(Bt601, Limited);
+        assert_eq!(m.apply([0, 0, 0]), [16, 128, 128]);
+        assert_eq!(m.apply([255, 255, 255]), [235, 128, 128]);
+        let m = ColorMatrix::rgb_to_yuv(Bt709, Full);
+        assert_eq!(m.apply([255, 255, 255]), [255, 128, 128]);
+        let id = m.then(&m.inverse().unwrap());
+        assert_eq!(id.apply([12, 200, 99]), [12, 200, 99]);
+    }
+
+    #[test]
+    fn test_color_matrix_preset() {
+        use ColorRange::*;
+        use ColorStandard::*;
+        let mode = ColorMatrix::yuv_to_rgb(Bt709, Limited).preset();
+        assert!(matches!(mode, Some(RgaColorSpaceMode::Yuv2RgbMode2)));
+        let mode = ColorMatrix::yuv_to_yuv((Bt601, Full), (Bt709, Limited)).preset();
+        assert!(matches!(
+            mode,
+            Some(RgaColorSpaceMode::Yuv2Yuv601Full709Limit)
+        ));
+        assert!(ColorMatrix::yuv_to_rgb(Bt2020, Limited).preset().is_none());
+
+        let (coe, off) = ColorMatrix::rgb_to_yuv(Bt601, Full).to_fixed().unwrap();
+        assert_eq!(coe[0], [306, 601, 117]);
+        assert_eq!(off, [0, 128 << CSC_FRAC_BITS, 128 << CSC_FRAC_BITS]);
+        let mut big = ColorMatrix::rgb_to_yuv(Bt601, Full);
+        big.coeffs[0][0] = 40.0;
+        assert!(big.to_fixed().is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "v1_2_3", not(feature = "v1_8_0")))]
+    fn test_color_matrix_to_raw() {
+        use ColorRange::*;
+        use ColorStandard::*;
+        let raw = ColorMatrix::rgb_to_yuv(Bt601, Full).to_raw().unwrap();
+        assert_eq!(raw.flag, 1);
+        let coe_y = raw.coe_y;
+        assert_eq!(
+            (coe_y.r_v, coe_y.g_y, coe_y.b_u, coe_y.off),
+            (306, 601, 117, 0)
+        );
+
+        // YUV 输入按 V、Y、U 排列。
+        let m = ColorMatrix::yuv_to_rgb(Bt2020, Limited);
+        let (coe, _) = m.to_fixed().unwrap();
+        let coe_y = m.to_raw().unwrap().coe_y;
+        assert_eq!(
+            (coe_y.r_v, coe_y.g_y, coe_y.b_u),
+            (coe[0][2], coe[0][0], coe[0][1])
+        );
+    }
+}
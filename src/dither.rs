@@ -0,0 +1,215 @@
+//! 降低色深时的有序抖动。
+//!
+use super::{ffi, soft, RgaPixelFormat, RgaRect};
+
+/// 默认使用的 4x4 Bayer 阈值矩阵。
+pub const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// 一个描述抖动目标色深的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum DitherMode {
+    /// RGB888 -> RGB666。
+    Rgb666 = 0,
+    /// RGB888 -> RGB565。
+    Rgb565 = 1,
+    /// RGB888 -> RGB555。
+    Rgb555 = 2,
+    /// RGB888 -> RGB444。
+    Rgb444 = 3,
+}
+
+impl DitherMode {
+    /// 返回适用于目标像素格式的模式，无需抖动时返回 None。
+    pub fn for_format(fmt: RgaPixelFormat) -> Option<Self> {
+        match soft::component_bits(fmt)? {
+            [5, 6, 5, _] => Some(Self::Rgb565),
+            [5, 5, 5, _] => Some(Self::Rgb555),
+            [4, 4, 4, _] => Some(Self::Rgb444),
+            _ => None,
+        }
+    }
+
+    /// 返回 R、G、B 分量的目标位宽。
+    pub fn bits(self) -> [u32; 3] {
+        match self {
+            Self::Rgb666 => [6, 6, 6],
+            Self::Rgb565 => [5, 6, 5],
+            Self::Rgb555 => [5, 5, 5],
+            Self::Rgb444 => [4, 4, 4],
+        }
+    }
+}
+
+/// 一个描述有序抖动参数的类型。
+///
+/// 阈值查找表为 4x4 的 4 位数值，`lut0` 用于 R、B 分量，`lut1` 用于 G 分量。
+/// 对于被截断 `k` 位的分量，像素在截断前加上 `lut[y % 4][x % 4] * 2^k / 16`。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Dither {
+    pub mode: DitherMode,
+    pub lut0: [[u8; 4]; 4],
+    pub lut1: [[u8; 4]; 4],
+}
+
+impl Dither {
+    /// 使用默认的 Bayer 查找表创建。
+    pub fn new(mode: DitherMode) -> Self {
+        Self {
+            mode,
+            lut0: BAYER_4X4,
+            lut1: BAYER_4X4,
+        }
+    }
+
+    /// 设置查找表。
+    pub fn with_luts(mut self, lut0: [[u8; 4]; 4], lut1: [[u8; 4]; 4]) -> Self {
+        self.lut0 = lut0;
+        self.lut1 = lut1;
+        self
+    }
+
+    /// 将查找表打包为低、高两个 32 位字，每项 4 位，按行优先排列。
+    pub fn pack_lut(lut: &[[u8; 4]; 4]) -> (i32, i32) {
+        let mut v = 0u64;
+        for (i, e) in lut.iter().flatten().enumerate() {
+            v |= ((*e & 0xf) as u64) << (i * 4);
+        }
+        (v as u32 as i32, (v >> 32) as u32 as i32)
+    }
+
+    /// 转换为硬件参数。
+    pub fn to_raw(&self) -> ffi::rga_dither_t {
+        let (lut0_l, lut0_h) = Self::pack_lut(&self.lut0);
+        let (lut1_l, lut1_h) = Self::pack_lut(&self.lut1);
+        ffi::rga_dither_t {
+            enable: 1,
+            mode: self.mode as i32,
+            lut0_l,
+            lut0_h,
+            lut1_l,
+            lut1_h,
+        }
+    }
+
+    /// 在 CPU 上对 `(x, y)` 处的像素进行抖动，返回已截断低位的 8 位分量。
+    pub fn apply_pixel(&self, x: i32, y: i32, rgba: [u8; 4]) -> [u8; 4] {
+        let (i, j) = ((y & 3) as usize, (x & 3) as usize);
+        let mut out = rgba;
+        for (c, bits) in self.mode.bits().iter().enumerate() {
+            let lost = 8 - bits;
+            let t = if c == 1 {
+                self.lut1[i][j]
+            } else {
+                self.lut0[i][j]
+            } as u32;
+            let v = (rgba[c] as u32 + ((t << lost) >> 4)).min(0xff);
+            out[c] = ((v >> lost) << lost) as u8;
+        }
+        out
+    }
+
+    /// 在 CPU 上将 `src` 图像抖动转换到同样大小的 `dst` 图像。
+    ///
+    /// # Panics
+    /// 当格式不受 [`soft`] 支持时触发。
+    pub fn apply(&self, src: &[u8], src_rect: &RgaRect, dst: &mut [u8], dst_rect: &RgaRect) {
+        for y in 0..dst_rect.height.min(src_rect.height) {
+            for x in 0..dst_rect.width.min(src_rect.width) {
+                let px = self.apply_pixel(x, y, soft::read(src, src_rect, x, y));
+                soft::write(dst, dst_rect, x, y, px);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::soft::SoftImage;
+
+    #[test]
+    fn test_dither_to_raw() {
+        let raw = Dither::new(DitherMode::Rgb565).to_raw();
+        assert_eq!(raw.enable, 1);
+        assert_eq!(raw.mode, 1);
+        assert_eq!(raw.lut0_l as u32, 0x6e4c_a280);
+        assert_eq!(raw.lut0_h as u32, 0x5d7f_91b3);
+        assert_eq!((raw.lut1_l, raw.lut1_h), (raw.lut0_l, raw.lut0_h));
+    }
+
+    #[test]
+    fn test_dither_mode_for_format() {
+        assert_eq!(
+            DitherMode::for_format(RgaPixelFormat::Rgb565),
+            Some(DitherMode::Rgb565)
+        );
+        assert_eq!(
+            DitherMode::for_format(RgaPixelFormat::Rgba5551),
+            Some(DitherMode::Rgb555)
+        );
+        assert_eq!(
+            DitherMode::for_format(RgaPixelFormat::Rgba4444),
+            Some(DitherMode::Rgb444)
+        );
+        assert_eq!(DitherMode::for_format(RgaPixelFormat::Rgba8888), None);
+    }
+
+    #[test]
+    fn test_dither_reduces_banding() {
+        // 16 级灰阶，每个色阶取 4x4 像素块的均值与原值比较。
+        let d = Dither::new(DitherMode::Rgb565);
+        let (mut plain, mut dithered) = (0f64, 0f64);
+        for v in 100..116u8 {
+            let (mut acc_p, mut acc_d) = (0f64, 0f64);
+            for y in 0..4 {
+                for x in 0..4 {
+                    acc_p += (v & !7) as f64;
+                    acc_d += d.apply_pixel(x, y, [v, v, v, 255])[0] as f64;
+                }
+            }
+            plain += (acc_p / 16.0 - v as f64).abs();
+            dithered += (acc_d / 16.0 - v as f64).abs();
+        }
+        assert!(dithered < plain / 4.0);
+    }
+
+    #[test]
+    fn test_dither_apply() {
+        // RGB565 下 R、B 加上 t / 2 后截断低 3 位，G 加上 t / 4 后截断低 2 位。
+        // 101 + 3 跨过 104，130 + 2 跨过 132，7 + 1 跨过 8，即分别在 t >= 6、8、2 时进位。
+        let mut src = SoftImage::new(4, 4, RgaPixelFormat::Rgb888);
+        for y in 0..4 {
+            for x in 0..4 {
+                src.put(x, y, [101, 130, 7, 255]);
+            }
+        }
+        let mut dst = SoftImage::new(4, 4, RgaPixelFormat::Rgb888);
+        let d = Dither::new(DitherMode::Rgb565);
+        d.apply(&src.data, &src.rect, &mut dst.data, &dst.rect);
+        let r = [
+            [96, 104, 96, 104],
+            [104, 96, 104, 104],
+            [96, 104, 96, 104],
+            [104, 104, 104, 96],
+        ];
+        let g = [
+            [128, 132, 128, 132],
+            [132, 128, 132, 128],
+            [128, 132, 128, 132],
+            [132, 128, 132, 128],
+        ];
+        let b = [[0, 8, 8, 8], [8, 8, 8, 8], [8, 8, 0, 8], [8, 8, 8, 8]];
+        for y in 0..4 {
+            for x in 0..4 {
+                let (i, j) = (y as usize, x as usize);
+                assert_eq!(dst.get(x, y)[..3], [r[i][j], g[i][j], b[i][j]]);
+            }
+        }
+
+        // 饱和值不会溢出，阈值最大时 0 仍不进位。
+        assert_eq!(d.apply_pixel(3, 0, [255; 4]), [248, 252, 248, 255]);
+        assert_eq!(d.apply_pixel(0, 3, [0, 0, 0, 9]), [0, 0, 0, 9]);
+        assert_eq!(d.apply_pixel(0, 3, [1, 1, 1, 9]), [8, 4, 8, 9]);
+    }
+}
@@ -0,0 +1,182 @@
+//! 矩形边框及直线绘制。
+//!
+//! librga 的 `rga_info` 接口未提供 `line_draw_t`，因此没有硬件画线路径，线条均分解为细长矩形，
+//! 再通过 [`Rga::fill_rects`] 合并提交：水平、竖直线条及矩形边框的每条边各为一次填充，
+//! 斜线则沿长轴逐段填充，填充次数随短轴方向的跨度增长。对于 YUV 图像，短轴方向按色度采样间隔分段，
+//! 各段区域按 [`RgaAlignPolicy`] 对齐。
+//!
+//! [`RgaAlignPolicy`]: crate::RgaAlignPolicy
+use super::geom::invalid_input;
+use super::{Point, Rect, Rga, RgaInfo, RgaPixelFormat};
+use std::io;
+
+/// 返回宽度为 `thickness` 的内侧边框所分解的区域。
+fn outline_rects(rect: &Rect, thickness: i32) -> Vec<Rect> {
+    if thickness * 2 >= rect.width || thickness * 2 >= rect.height {
+        return vec![*rect];
+    }
+    let inner = rect.height - thickness * 2;
+    vec![
+        Rect::new(rect.x, rect.y, rect.width, thickness),
+        Rect::new(rect.x, rect.bottom() - thickness, rect.width, thickness),
+        Rect::new(rect.x, rect.y + thickness, thickness, inner),
+        Rect::new(
+            rect.right() - thickness,
+            rect.y + thickness,
+            thickness,
+            inner,
+        ),
+    ]
+}
+
+/// 返回从 `p0` 到 `p1`、短轴方向宽度为 `width` 的线段所分解的区域，
+/// 短轴方向每 `step` 个像素为一段。
+fn line_rects(p0: Point, p1: Point, width: i32, step: i32) -> Vec<Rect> {
+    let (dx, dy) = (p1.x - p0.x, p1.y - p0.y);
+    // 统一为沿长轴 (u) 递增、短轴为 v 的情形，最后再交换回来。
+    let steep = dy.abs() > dx.abs();
+    let (u0, v0, du, dv) = if steep {
+        (p0.y, p0.x, dy, dx)
+    } else {
+        (p0.x, p0.y, dx, dy)
+    };
+    let (u0, v0, du, dv) = if du < 0 {
+        (u0 + du, v0 + dv, -du, -dv)
+    } else {
+        (u0, v0, du, dv)
+    };
+    let thickness = width.max(step);
+
+    let mut rects = Vec::new();
+    let mut span: Option<(i32, i32, i32)> = None; // (起点 u, 终点 u, 段号)
+    for i in 0..=du {
+        // 四舍五入：v = v0 + round(i * dv / du)
+        let v = if du == 0 {
+            v0
+        } else {
+            v0 + (2 * i * dv + du * dv.signum()) / (2 * du)
+        };
+        let band = v.div_euclid(step);
+        span = match span {
+            Some((start, _, b)) if b == band => Some((start, u0 + i, b)),
+            Some(done) => {
+                rects.push(done);
+                Some((u0 + i, u0 + i, band))
+            }
+            None => Some((u0 + i, u0 + i, band)),
+        };
+    }
+    rects.extend(span);
+
+    rects
+        .into_iter()
+        .map(|(start, end, band)| {
+            let v = band * step - (thickness - step) / 2;
+            if steep {
+                Rect::new(v, start, thickness, end - start + 1)
+            } else {
+                Rect::new(start, v, end - start + 1, thickness)
+            }
+        })
+        .collect()
+}
+
+/// 返回批量填充结果中的首个错误。
+fn first_error(results: Vec<Result<(), io::Error>>) -> Result<(), io::Error> {
+    results.into_iter().collect()
+}
+
+impl Rga {
+    /// 在区域 `rect` 内侧绘制宽度为 `thickness` 的矩形边框。
+    pub fn draw_rect_outline(
+        &self,
+        dst: &RgaInfo,
+        rect: Rect,
+        thickness: i32,
+        color: u32,
+    ) -> Result<(), io::Error> {
+        if thickness <= 0 {
+            return Err(invalid_input("outline thickness must be positive"));
+        }
+        let rects: Vec<_> = outline_rects(&rect, thickness)
+            .into_iter()
+            .map(|r| (r, color))
+            .collect();
+        first_error(self.fill_rects(dst, &rects)?)
+    }
+
+    /// 绘制从 `p0` 到 `p1`（包含端点）、宽度为 `width` 的直线。
+    ///
+    /// 宽度沿短轴方向计算，斜线的实际粗细约为 `width * cos(θ)`。
+    ///
+    /// 水平、竖直线条为一次矩形填充；斜线在短轴方向每跨过一个像素（YUV 图像为一个色度采样间隔）
+    /// 多一次填充，45 度斜线的填充次数与像素数相同。
+    pub fn draw_line(
+        &self,
+        dst: &RgaInfo,
+        p0: Point,
+        p1: Point,
+        width: i32,
+        color: u32,
+    ) -> Result<(), io::Error> {
+        if width <= 0 {
+            return Err(invalid_input("line width must be positive"));
+        }
+        let fmt = RgaPixelFormat::from_raw(dst.rect.format);
+        let (ax, ay) = fmt.chroma_subsampling().unwrap_or((1, 1));
+        let step = if (p1.y - p0.y).abs() > (p1.x - p0.x).abs() {
+            ax
+        } else {
+            ay
+        };
+        let rects: Vec<_> = line_rects(p0, p1, width, step)
+            .into_iter()
+            .map(|r| (r, color))
+            .collect();
+        first_error(self.fill_rects(dst, &rects)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn covered(rects: &[Rect], x: i32, y: i32) -> bool {
+        rects.iter().any(|r| r.contains(&Rect::new(x, y, 1, 1)))
+    }
+
+    #[test]
+    fn test_outline_rects() {
+        let rect = Rect::new(2, 3, 10, 8);
+        let rects = outline_rects(&rect, 2);
+        assert_eq!(rects.len(), 4);
+        for y in 0..16 {
+            for x in 0..16 {
+                let inside = rect.contains(&Rect::new(x, y, 1, 1));
+                let inner = Rect::new(4, 5, 6, 4).contains(&Rect::new(x, y, 1, 1));
+                assert_eq!(covered(&rects, x, y), inside && !inner, "({}, {})", x, y);
+            }
+        }
+        assert_eq!(outline_rects(&rect, 4), vec![rect]);
+    }
+
+    #[test]
+    fn test_line_rects() {
+        let h = line_rects(Point::new(9, 4), Point::new(1, 4), 3, 1);
+        assert_eq!(h, vec![Rect::new(1, 3, 9, 3)]);
+        let v = line_rects(Point::new(6, 2), Point::new(6, 30), 2, 2);
+        assert_eq!(v, vec![Rect::new(6, 2, 2, 29)]);
+
+        // 45 度斜线的每个像素各占一段，两端点均被覆盖。
+        let d = line_rects(Point::new(0, 0), Point::new(5, 5), 1, 1);
+        assert_eq!(d.len(), 6);
+        assert!((0..6).all(|i| covered(&d, i, i)));
+
+        // NV12 的段按 2 行对齐。
+        let nv12 = line_rects(Point::new(0, 1), Point::new(20, 8), 2, 2);
+        for r in &nv12 {
+            assert_eq!((r.y % 2, r.height), (0, 2));
+        }
+        assert!(covered(&nv12, 0, 1) && covered(&nv12, 20, 8));
+    }
+}
@@ -0,0 +1,138 @@
+//! 以全局 Alpha 实现的两帧淡入淡出。
+//!
+use super::alpha::BLEND_SRC_OVER_STRAIGHT;
+use super::geom::{bounds_of, invalid_input};
+use super::{Rga, RgaInfo};
+use std::io;
+
+/// 将混合进度 `t` 转换为全局 Alpha，`t` 被截断到 `[0, 1]`。
+pub fn crossfade_alpha(t: f32) -> u8 {
+    (t.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+impl Rga {
+    /// 按进度 `t` 混合 `a`、`b` 两幅大小一致的图像写入 `dst` 图像。
+    ///
+    /// `t` 为 0 时输出 `a`，为 1 时输出 `b`。
+    ///
+    /// 硬件以 `b` 的逐像素 Alpha 与全局 Alpha 之积作为混合权重，因此 `b` 须为不透明图像
+    /// （Alpha 均为 255 或格式不含 Alpha），此时结果与 [`soft::crossfade`] 一致；
+    /// 否则 `b` 中半透明的像素会偏向 `a`。
+    ///
+    /// [`soft::crossfade`]: super::soft::crossfade
+    pub fn crossfade(
+        &self,
+        a: &RgaInfo,
+        b: &RgaInfo,
+        dst: &mut RgaInfo,
+        t: f32,
+    ) -> Result<(), io::Error> {
+        let size = |info| bounds_of(info).map(|r| (r.width, r.height));
+        let dst_size = size(dst)?;
+        if size(a)? != dst_size || size(b)? != dst_size {
+            return Err(invalid_input("crossfade images must have the same size"));
+        }
+        // 全局 Alpha 位于 `blend` 的 16 ~ 23 位。
+        let alpha = crossfade_alpha(t) as u32;
+        let src = RgaInfo {
+            blend: BLEND_SRC_OVER_STRAIGHT | (alpha << 16),
+            ..*b
+        };
+        let mut extra = *a;
+        self.blit(&src, dst, Some(&mut extra))
+    }
+
+    /// 生成从 `a` 到 `b` 的过渡序列，依次写入 `frames` 中的各图像。
+    ///
+    /// 共 N 帧时第 i 帧（从 0 开始）的进度为 `(i + 1) / N`，最后一帧与 `b` 相同。
+    /// 与 [`Rga::crossfade`] 一样要求 `b` 为不透明图像。
+    pub fn crossfade_sequence(
+        &self,
+        a: &RgaInfo,
+        b: &RgaInfo,
+        frames: &mut [RgaInfo],
+    ) -> Result<(), io::Error> {
+        let n = frames.len();
+        for (i, dst) in frames.iter_mut().enumerate() {
+            self.crossfade(a, b, dst, sequence_progress(i, n))?;
+        }
+        Ok(())
+    }
+}
+
+/// 返回共 `n` 帧的过渡序列中第 `i` 帧的进度。
+fn sequence_progress(i: usize, n: usize) -> f32 {
+    (i + 1) as f32 / n as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::soft::{self, crossfade_pixel, SoftImage};
+    use crate::RgaPixelFormat;
+
+    #[test]
+    fn test_crossfade_reference() {
+        assert_eq!(crossfade_alpha(0.0), 0);
+        assert_eq!(crossfade_alpha(0.5), 128);
+        assert_eq!(crossfade_alpha(2.0), 255);
+        let (a, b) = ([0, 100, 255, 255], [255, 200, 0, 0]);
+        assert_eq!(crossfade_pixel(a, b, 0), a);
+        assert_eq!(crossfade_pixel(a, b, 255), b);
+        assert_eq!(crossfade_pixel(a, b, 128), [128, 150, 127, 127]);
+
+        let mut ia = SoftImage::new(3, 2, RgaPixelFormat::Rgb565);
+        let mut ib = SoftImage::new(3, 2, RgaPixelFormat::Rgba8888);
+        ia.put(2, 1, [0xf8, 0xfc, 0xf8, 0xff]);
+        ib.put(2, 1, [0, 0, 0, 0xff]);
+        let mut out = SoftImage::new(3, 2, RgaPixelFormat::Rgba8888);
+        soft::crossfade(
+            &ia.data,
+            &ia.rect,
+            &ib.data,
+            &ib.rect,
+            &mut out.data,
+            &out.rect,
+            64,
+        );
+        assert_eq!(
+            out.get(2, 1),
+            crossfade_pixel([255, 255, 255, 255], [0, 0, 0, 255], 64)
+        );
+    }
+
+    #[test]
+    fn test_crossfade_sequence_progress() {
+        let progress = |n| (0..n).map(|i| sequence_progress(i, n)).collect::<Vec<_>>();
+        assert_eq!(progress(1), [1.0]);
+        assert_eq!(progress(4), [0.25, 0.5, 0.75, 1.0]);
+        let alphas = progress(4)
+            .into_iter()
+            .map(crossfade_alpha)
+            .collect::<Vec<_>>();
+        assert_eq!(alphas, [64, 128, 191, 255]);
+        let alphas = progress(3)
+            .into_iter()
+            .map(crossfade_alpha)
+            .collect::<Vec<_>>();
+        assert_eq!(alphas, [85, 170, 255]);
+    }
+
+    #[test]
+    fn test_crossfade_size_mismatch() {
+        let rga = Rga::new().unwrap();
+        let small = SoftImage::new(4, 4, RgaPixelFormat::Rgba8888);
+        let large = SoftImage::new(8, 4, RgaPixelFormat::Rgba8888);
+        let info = |img: &SoftImage| RgaInfo {
+            fd: -1,
+            rect: img.rect,
+            format: img.rect.format,
+            ..Default::default()
+        };
+        let mut frames = [info(&large), info(&large)];
+        let err = rga
+            .crossfade_sequence(&info(&small), &info(&large), &mut frames)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
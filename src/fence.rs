@@ -0,0 +1,229 @@
+//! 异步提交及同步栅栏。
+//!
+use super::{ffi, Rga, RgaInfo};
+use std::io;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+use std::time::Duration;
+
+/// 一个描述同步栅栏的类型，拥有异步操作返回的 `out_fence_fd`。
+///
+/// 栅栏在对应操作完成时变为可读，可直接交给 GPU、编码器或显示模块等待，
+/// 也可作为下一次 RGA 操作的输入栅栏。
+#[derive(Debug)]
+pub struct RgaFence {
+    fd: Option<OwnedFd>,
+}
+
+impl RgaFence {
+    /// 创建一个已触发的栅栏，其描述符为 -1。
+    pub fn signaled() -> Self {
+        Self { fd: None }
+    }
+
+    /// 取走操作信息中的 `out_fence_fd`，并将其重置为 -1。
+    pub fn take(info: &mut RgaInfo) -> Self {
+        let fd = std::mem::replace(&mut info.out_fence_fd, -1);
+        if fd < 0 {
+            Self::signaled()
+        } else {
+            unsafe { Self::from_raw_fd(fd) }
+        }
+    }
+
+    /// 等待栅栏触发，超时返回 false；`timeout` 为 None 时一直等待。
+    pub fn wait(&self, timeout: Option<Duration>) -> Result<bool, io::Error> {
+        let fd = match &self.fd {
+            Some(fd) => fd.as_raw_fd(),
+            None => return Ok(true),
+        };
+        let timeout = poll_timeout(timeout);
+        let mut pfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        loop {
+            match unsafe { libc::poll(&mut pfd, 1, timeout) } {
+                0 => return Ok(false),
+                n if n > 0 => {
+                    if pfd.revents & (libc::POLLERR | libc::POLLNVAL) != 0 {
+                        return Err(io::Error::from_raw_os_error(libc::EINVAL));
+                    }
+                    return Ok(true);
+                }
+                _ => {
+                    let err = io::Error::last_os_error();
+                    if err.kind() != io::ErrorKind::Interrupted {
+                        return Err(err);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 当栅栏已触发时返回 true。
+    pub fn is_signaled(&self) -> Result<bool, io::Error> {
+        self.wait(Some(Duration::ZERO))
+    }
+
+    /// 返回借用的描述符，已触发的栅栏返回 None。
+    pub fn as_fd(&self) -> Option<BorrowedFd<'_>> {
+        self.fd.as_ref().map(|fd| fd.as_fd())
+    }
+
+    /// 复制一个引用同一栅栏的新对象。
+    pub fn try_clone(&self) -> Result<Self, io::Error> {
+        match &self.fd {
+            Some(fd) => Ok(Self {
+                fd: Some(fd.try_clone()?),
+            }),
+            None => Ok(Self::signaled()),
+        }
+    }
+}
+
+impl AsRawFd for RgaFence {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_ref().map_or(-1, |fd| fd.as_raw_fd())
+    }
+}
+
+impl FromRawFd for RgaFence {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        Self {
+            fd: Some(OwnedFd::from_raw_fd(fd)),
+        }
+    }
+}
+
+impl IntoRawFd for RgaFence {
+    fn into_raw_fd(self) -> RawFd {
+        self.fd.map_or(-1, |fd| fd.into_raw_fd())
+    }
+}
+
+/// 将超时转换为 `poll` 的毫秒数，不足 1 毫秒的部分向上取整，避免提前超时。
+fn poll_timeout(timeout: Option<Duration>) -> i32 {
+    timeout.map_or(-1, |t| {
+        t.as_nanos().div_ceil(1_000_000).min(i32::MAX as u128) as i32
+    })
+}
+
+impl From<OwnedFd> for RgaFence {
+    fn from(fd: OwnedFd) -> Self {
+        Self { fd: Some(fd) }
+    }
+}
+
+impl Rga {
+    /// 以异步模式对象图像进行比特操作，返回操作完成时触发的栅栏。
+    ///
+    /// 当指定 `in_fence` 时，硬件在其触发后才开始处理，调用线程不会阻塞。
+    /// 操作通过 `dst` 的副本提交，`dst` 本身的同步模式及栅栏字段保持不变。
+    pub fn blit_fenced(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        extra_src: Option<&mut RgaInfo>,
+        in_fence: Option<&RgaFence>,
+    ) -> Result<RgaFence, io::Error> {
+        let src = RgaInfo {
+            sync_mode: ffi::RGA_BLIT_ASYNC as i32,
+            ..*src
+        };
+        let mut dst = RgaInfo {
+            sync_mode: ffi::RGA_BLIT_ASYNC as i32,
+            in_fence_fd: in_fence.map_or(-1, |f| f.as_raw_fd()),
+            out_fence_fd: -1,
+            ..*dst
+        };
+        self.blit(&src, &mut dst, extra_src)?;
+        Ok(RgaFence::take(&mut dst))
+    }
+
+    /// 以异步模式使用指定颜色填充图像，返回操作完成时触发的栅栏。
+    pub fn fill_fenced(
+        &self,
+        dst: &RgaInfo,
+        color: u32,
+        in_fence: Option<&RgaFence>,
+    ) -> Result<RgaFence, io::Error> {
+        self.check_schedule(dst)?;
+        let mut dst = RgaInfo {
+            sync_mode: ffi::RGA_BLIT_ASYNC as i32,
+            in_fence_fd: in_fence.map_or(-1, |f| f.as_raw_fd()),
+            out_fence_fd: -1,
+            color: color as i32,
+            ..*dst
+        };
+        match unsafe { ffi::c_RkRgaColorFill(&mut dst) } {
+            0 => Ok(RgaFence::take(&mut dst)),
+            err => Err(io::Error::from_raw_os_error(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fence_wait() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let fence = unsafe { RgaFence::from_raw_fd(fds[0]) };
+        let writer = unsafe { OwnedFd::from_raw_fd(fds[1]) };
+        assert!(!fence.is_signaled().unwrap());
+        assert!(!fence.wait(Some(Duration::from_millis(1))).unwrap());
+        assert_eq!(
+            unsafe { libc::write(writer.as_raw_fd(), [1u8].as_ptr().cast(), 1) },
+            1
+        );
+        assert!(fence.try_clone().unwrap().wait(None).unwrap());
+        assert_eq!(fence.as_raw_fd(), fds[0]);
+    }
+
+    #[test]
+    fn test_poll_timeout() {
+        assert_eq!(poll_timeout(None), -1);
+        assert_eq!(poll_timeout(Some(Duration::ZERO)), 0);
+        assert_eq!(poll_timeout(Some(Duration::from_nanos(1))), 1);
+        assert_eq!(poll_timeout(Some(Duration::from_micros(1500))), 2);
+        assert_eq!(poll_timeout(Some(Duration::from_millis(3))), 3);
+        assert_eq!(poll_timeout(Some(Duration::MAX)), i32::MAX);
+    }
+
+    #[test]
+    fn test_blit_fenced_keeps_dst() {
+        use crate::{RgaBuffer, RgaInfoBuilder, RgaPixelFormat, RgaRectBuilder};
+        use std::sync::Arc;
+
+        let rga = Arc::new(Rga::new().unwrap());
+        let rect = RgaRectBuilder::new()
+            .size(16, 16)
+            .format(RgaPixelFormat::Rgba8888)
+            .build();
+        let src_bo = RgaBuffer::with_rect(Arc::clone(&rga), &rect).unwrap();
+        let dst_bo = RgaBuffer::with_rect(Arc::clone(&rga), &rect).unwrap();
+        let src = *RgaInfoBuilder::new().bo(&src_bo).rect(&rect).build();
+        let mut dst = *RgaInfoBuilder::new().bo(&dst_bo).rect(&rect).build();
+        dst.sync_mode = ffi::RGA_BLIT_SYNC as i32;
+        dst.out_fence_fd = -1;
+        let fence = rga.blit_fenced(&src, &mut dst, None, None).unwrap();
+        assert!(fence.wait(None).unwrap());
+        // 之后对同一 `dst` 的普通比特操作仍以同步模式执行。
+        assert_eq!(dst.sync_mode, ffi::RGA_BLIT_SYNC as i32);
+        assert_eq!((dst.in_fence_fd, dst.out_fence_fd), (-1, -1));
+    }
+
+    #[test]
+    fn test_fence_take() {
+        let mut info = RgaInfo {
+            out_fence_fd: -1,
+            ..Default::default()
+        };
+        let fence = RgaFence::take(&mut info);
+        assert!(fence.is_signaled().unwrap());
+        assert_eq!(fence.as_raw_fd(), -1);
+    }
+}
@@ -0,0 +1,215 @@
+//! 批量填充矩形区域。
+//!
+use super::geom::{bounds_of, with_region};
+use super::{Rect, Rga, RgaInfo, RgaPixelFormat};
+use std::io;
+
+/// 一个描述合并后单次填充操作的类型。
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Piece {
+    rect: Rect,
+    color: u32,
+    /// 该操作覆盖的输入区域索引。
+    sources: Vec<usize>,
+}
+
+/// 当两个互不相交的区域相邻且并集为矩形时返回并集。
+fn union_adjacent(a: &Rect, b: &Rect) -> Option<Rect> {
+    if a.x == b.x && a.width == b.width && (a.bottom() == b.y || b.bottom() == a.y) {
+        Some(Rect::new(a.x, a.y.min(b.y), a.width, a.height + b.height))
+    } else if a.y == b.y && a.height == b.height && (a.right() == b.x || b.right() == a.x) {
+        Some(Rect::new(a.x.min(b.x), a.y, a.width + b.width, a.height))
+    } else {
+        None
+    }
+}
+
+/// 规划填充操作：后面的区域覆盖前面的区域，去除被完全覆盖的部分，并合并相邻的同色区域。
+///
+/// 返回的操作须按顺序执行。可见部分需拆分为多个矩形时整体填充，被覆盖的部分由后面的操作重新填充，
+/// 因此调用次数不会多于区域数量。`rects` 中为 None 的项不参与填充。
+fn plan(rects: &[Option<(Rect, u32)>]) -> Vec<Piece> {
+    let mut pieces: Vec<Piece> = Vec::new();
+    for (i, &(rect, color)) in rects
+        .iter()
+        .enumerate()
+        .filter_map(|(i, r)| Some((i, r.as_ref()?)))
+    {
+        let mut visible = vec![rect];
+        for &(above, _) in rects[i + 1..].iter().flatten() {
+            visible = visible.iter().flat_map(|r| r.subtract(&above)).collect();
+        }
+        if visible.len() > 1 {
+            visible = vec![rect];
+        }
+        pieces.extend(visible.into_iter().map(|rect| Piece {
+            rect,
+            color,
+            sources: vec![i],
+        }));
+    }
+
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'outer: for i in 0..pieces.len() {
+            for j in i + 1..pieces.len() {
+                // 提前填充第 j 个操作时不能被其间的操作覆盖。
+                if pieces[i].color != pieces[j].color
+                    || pieces[i + 1..j]
+                        .iter()
+                        .any(|p| p.rect.intersect(&pieces[j].rect).is_some())
+                {
+                    continue;
+                }
+                if let Some(rect) = union_adjacent(&pieces[i].rect, &pieces[j].rect) {
+                    let other = pieces.remove(j);
+                    pieces[i].rect = rect;
+                    for s in other.sources {
+                        if !pieces[i].sources.contains(&s) {
+                            pieces[i].sources.push(s);
+                        }
+                    }
+                    merged = true;
+                    break 'outer;
+                }
+            }
+        }
+    }
+    pieces
+}
+
+fn copy_error(err: &io::Error) -> io::Error {
+    match err.raw_os_error() {
+        Some(code) => io::Error::from_raw_os_error(code),
+        None => io::Error::new(err.kind(), err.to_string()),
+    }
+}
+
+impl Rga {
+    /// 使用各自的颜色填充多个区域，后面的区域覆盖前面的区域。
+    ///
+    /// 区域超出图像的部分被裁剪，被完全覆盖的部分不再填充，相邻的同色区域合并为一次填充，
+    /// 以减少 `c_RkRgaColorFill` 的调用次数。返回值与 `rects` 一一对应，
+    /// 记录各区域的填充结果；仅当 `dst` 本身无效时返回错误。
+    pub fn fill_rects(
+        &self,
+        dst: &RgaInfo,
+        rects: &[(Rect, u32)],
+    ) -> Result<Vec<Result<(), io::Error>>, io::Error> {
+        let bounds = bounds_of(dst)?;
+        let fmt = RgaPixelFormat::from_raw(dst.rect.format);
+        let policy = self.align_policy();
+
+        let mut results: Vec<Result<(), io::Error>> = Vec::with_capacity(rects.len());
+        let mut regions = Vec::with_capacity(rects.len());
+        for &(rect, color) in rects {
+            let region = rect
+                .intersect(&bounds)
+                .map(|r| policy.apply(r, fmt, &bounds))
+                .transpose();
+            match region {
+                Ok(region) => {
+                    results.push(Ok(()));
+                    regions.push(region.map(|r| (r, color)));
+                }
+                Err(err) => {
+                    results.push(Err(err));
+                    regions.push(None);
+                }
+            }
+        }
+
+        for piece in plan(&regions) {
+            if let Err(err) = self.fill(&with_region(dst, &piece.rect), piece.color) {
+                for &i in &piece.sources {
+                    if results[i].is_ok() {
+                        results[i] = Err(copy_error(&err));
+                    }
+                }
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paint(grid: &mut [u32], width: i32, rect: &Rect, color: u32) {
+        for y in rect.y..rect.bottom() {
+            for x in rect.x..rect.right() {
+                grid[(y * width + x) as usize] = color;
+            }
+        }
+    }
+
+    #[test]
+    fn test_plan_matches_painter() {
+        let rects = [
+            Some((Rect::new(0, 0, 32, 32), 1)),
+            Some((Rect::new(4, 4, 8, 8), 2)),
+            None,
+            Some((Rect::new(10, 2, 12, 20), 3)),
+            Some((Rect::new(16, 16, 16, 16), 2)),
+            Some((Rect::new(0, 24, 16, 8), 3)),
+        ];
+        let (mut expected, mut planned) = (vec![0u32; 32 * 32], vec![0u32; 32 * 32]);
+        for (rect, color) in rects.iter().flatten() {
+            paint(&mut expected, 32, rect, *color);
+        }
+        let pieces = plan(&rects);
+        assert!(pieces.len() <= 5);
+        for p in &pieces {
+            paint(&mut planned, 32, &p.rect, p.color);
+        }
+        assert_eq!(planned, expected);
+    }
+
+    #[test]
+    fn test_plan_call_count() {
+        // 背景中嵌有一个区域时先整体填充背景，而不是拆分为四个矩形。
+        let rects = [
+            Some((Rect::new(0, 0, 32, 32), 1)),
+            Some((Rect::new(8, 8, 8, 8), 2)),
+        ];
+        let pieces = plan(&rects);
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[0].rect, Rect::new(0, 0, 32, 32));
+        assert_eq!(pieces[1].rect, Rect::new(8, 8, 8, 8));
+
+        // 同色区域不能越过覆盖它的操作提前合并。
+        let rects = [
+            Some((Rect::new(0, 0, 8, 8), 1)),
+            Some((Rect::new(8, 0, 8, 16), 2)),
+            Some((Rect::new(8, 0, 8, 8), 1)),
+            Some((Rect::new(12, 12, 2, 2), 3)),
+        ];
+        let pieces = plan(&rects);
+        let mut planned = vec![0u32; 16 * 16];
+        for p in &pieces {
+            paint(&mut planned, 16, &p.rect, p.color);
+        }
+        let mut expected = vec![0u32; 16 * 16];
+        for (rect, color) in rects.iter().flatten() {
+            paint(&mut expected, 16, rect, *color);
+        }
+        assert_eq!(planned, expected);
+        assert_eq!(pieces.len(), 4);
+    }
+
+    #[test]
+    fn test_plan_merges_and_drops() {
+        // 相邻的同色区域合并为一次填充，被完全覆盖的区域不填充。
+        let rects = [
+            Some((Rect::new(2, 2, 4, 4), 9)),
+            Some((Rect::new(0, 0, 8, 8), 1)),
+            Some((Rect::new(8, 0, 8, 8), 1)),
+        ];
+        let pieces = plan(&rects);
+        assert_eq!(pieces.len(), 1);
+        assert_eq!(pieces[0].rect, Rect::new(0, 0, 16, 8));
+        assert_eq!(pieces[0].sources, vec![1, 2]);
+    }
+}
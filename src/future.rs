@@ -0,0 +1,315 @@
+//! 异步操作的 `Future` 支持。
+//!
+//! 默认使用一个基于 epoll 的后台反应器线程等待栅栏触发，不依赖任何异步运行时；
+//! 启用 `tokio` 特性时，可通过 [`RgaFence::readable`] 直接使用 tokio 的反应器。
+use super::{Rga, RgaFence, RgaInfo};
+use std::collections::HashMap;
+use std::future::{Future, IntoFuture};
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::task::{Context, Poll, Waker};
+
+/// 一个描述 epoll 反应器的类型。
+struct Reactor {
+    epfd: OwnedFd,
+    wakers: Mutex<HashMap<u64, Waker>>,
+    next_token: AtomicU64,
+}
+
+impl Reactor {
+    /// 返回全局反应器，首次调用时创建后台线程。
+    fn get() -> Result<&'static Self, io::Error> {
+        static REACTOR: OnceLock<Result<Reactor, i32>> = OnceLock::new();
+        let reactor = REACTOR.get_or_init(|| {
+            let epfd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+            if epfd < 0 {
+                return Err(io::Error::last_os_error().raw_os_error().unwrap_or(0));
+            }
+            Ok(Reactor {
+                epfd: unsafe { OwnedFd::from_raw_fd(epfd) },
+                wakers: Mutex::new(HashMap::new()),
+                next_token: AtomicU64::new(1),
+            })
+        });
+        match reactor {
+            Ok(reactor) => {
+                static STARTED: OnceLock<()> = OnceLock::new();
+                STARTED.get_or_init(|| {
+                    std::thread::Builder::new()
+                        .name("rkrga-reactor".into())
+                        .spawn(move || reactor.run())
+                        .expect("failed to spawn rkrga reactor thread");
+                });
+                Ok(reactor)
+            }
+            Err(err) => Err(io::Error::from_raw_os_error(*err)),
+        }
+    }
+
+    fn run(&self) {
+        let mut events = [libc::epoll_event { events: 0, u64: 0 }; 64];
+        loop {
+            let n = unsafe {
+                libc::epoll_wait(
+                    self.epfd.as_raw_fd(),
+                    events.as_mut_ptr(),
+                    events.len() as i32,
+                    -1,
+                )
+            };
+            if n < 0 {
+                continue;
+            }
+            let mut wakers = self.wakers.lock().unwrap();
+            let ready: Vec<_> = events[..n as usize]
+                .iter()
+                .filter_map(|ev| wakers.remove(&{ ev.u64 }))
+                .collect();
+            drop(wakers);
+            ready.into_iter().for_each(Waker::wake);
+        }
+    }
+
+    fn token(&self) -> u64 {
+        self.next_token.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// 以单次触发方式监听 `fd` 可读，触发时唤醒 `waker`。
+    fn register(&self, fd: RawFd, token: u64, waker: Waker, added: bool) -> Result<(), io::Error> {
+        self.wakers.lock().unwrap().insert(token, waker);
+        let mut ev = libc::epoll_event {
+            events: (libc::EPOLLIN | libc::EPOLLONESHOT) as u32,
+            u64: token,
+        };
+        let op = if added {
+            libc::EPOLL_CTL_MOD
+        } else {
+            libc::EPOLL_CTL_ADD
+        };
+        if unsafe { libc::epoll_ctl(self.epfd.as_raw_fd(), op, fd, &mut ev) } < 0 {
+            self.wakers.lock().unwrap().remove(&token);
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn deregister(&self, fd: RawFd, token: u64) {
+        unsafe {
+            libc::epoll_ctl(
+                self.epfd.as_raw_fd(),
+                libc::EPOLL_CTL_DEL,
+                fd,
+                std::ptr::null_mut(),
+            );
+        }
+        self.wakers.lock().unwrap().remove(&token);
+    }
+}
+
+/// 一个在栅栏触发时完成的 `Future`。
+#[derive(Debug)]
+pub struct RgaFenceFuture {
+    fence: Option<RgaFence>,
+    error: Option<io::Error>,
+    token: u64,
+    registered: bool,
+}
+
+impl RgaFenceFuture {
+    /// 创建等待 `fence` 的 `Future`。
+    pub fn new(fence: RgaFence) -> Self {
+        Self {
+            fence: Some(fence),
+            error: None,
+            token: 0,
+            registered: false,
+        }
+    }
+
+    /// 创建一个立即返回错误的 `Future`。
+    pub(crate) fn failed(err: io::Error) -> Self {
+        Self {
+            fence: None,
+            error: Some(err),
+            token: 0,
+            registered: false,
+        }
+    }
+
+    fn deregister(&mut self) {
+        if let (true, Some(fence)) = (self.registered, &self.fence) {
+            if let Ok(reactor) = Reactor::get() {
+                reactor.deregister(fence.as_raw_fd(), self.token);
+            }
+            self.registered = false;
+        }
+    }
+}
+
+impl Future for RgaFenceFuture {
+    type Output = Result<(), io::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(err) = self.error.take() {
+            return Poll::Ready(Err(err));
+        }
+        let fd = match &self.fence {
+            Some(fence) => match fence.is_signaled() {
+                Ok(false) => fence.as_raw_fd(),
+                res => {
+                    self.deregister();
+                    self.fence = None;
+                    return Poll::Ready(res.map(|_| ()));
+                }
+            },
+            None => return Poll::Ready(Ok(())),
+        };
+        let reactor = Reactor::get()?;
+        if self.token == 0 {
+            self.token = reactor.token();
+        }
+        reactor.register(fd, self.token, cx.waker().clone(), self.registered)?;
+        self.registered = true;
+        Poll::Pending
+    }
+}
+
+impl Drop for RgaFenceFuture {
+    fn drop(&mut self) {
+        self.deregister();
+    }
+}
+
+impl From<RgaFence> for RgaFenceFuture {
+    fn from(fence: RgaFence) -> Self {
+        Self::new(fence)
+    }
+}
+
+impl IntoFuture for RgaFence {
+    type Output = Result<(), io::Error>;
+    type IntoFuture = RgaFenceFuture;
+
+    fn into_future(self) -> Self::IntoFuture {
+        RgaFenceFuture::new(self)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl RgaFence {
+    /// 使用 tokio 的反应器等待栅栏触发，须在 tokio 运行时中调用。
+    pub async fn readable(self) -> Result<(), io::Error> {
+        use tokio::io::{unix::AsyncFd, Interest};
+
+        if self.as_raw_fd() < 0 {
+            return Ok(());
+        }
+        // SAFETY: RgaFence 拥有其描述符，在 AsyncFd 的生命周期内保持打开且不变。
+        let fd = unsafe { AsyncFd::register_with_interest(self, Interest::READABLE)? };
+        let _guard = fd.readable().await?;
+        Ok(())
+    }
+}
+
+impl Rga {
+    /// 以异步模式对象图像进行比特操作，返回在操作完成时就绪的 `Future`。
+    ///
+    /// 操作在调用时即已提交，提交失败的错误在首次轮询时返回。
+    pub fn blit_async(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        extra_src: Option<&mut RgaInfo>,
+        in_fence: Option<&RgaFence>,
+    ) -> RgaFenceFuture {
+        match self.blit_fenced(src, dst, extra_src, in_fence) {
+            Ok(fence) => RgaFenceFuture::new(fence),
+            Err(err) => RgaFenceFuture::failed(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::task::Wake;
+    use std::thread::Thread;
+    use std::time::Duration;
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = std::pin::pin!(fut);
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match fut.as_mut().poll(&mut cx) {
+                Poll::Ready(out) => return out,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    /// 返回一个以管道读端模拟的栅栏，及其写端。
+    fn pipe_fence() -> (RgaFence, OwnedFd) {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        unsafe { (RgaFence::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) }
+    }
+
+    fn signal(writer: OwnedFd) {
+        assert_eq!(
+            unsafe { libc::write(writer.as_raw_fd(), [1u8].as_ptr().cast(), 1) },
+            1
+        );
+    }
+
+    #[test]
+    fn test_fence_future() {
+        let (fence, writer) = pipe_fence();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            signal(writer);
+        });
+        block_on(fence.into_future()).unwrap();
+        handle.join().unwrap();
+        block_on(RgaFence::signaled().into_future()).unwrap();
+
+        // 未完成即被丢弃的 Future 应从反应器中注销。
+        let (fence, _writer) = pipe_fence();
+        let mut fut = RgaFenceFuture::new(fence);
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        assert!(Pin::new(&mut fut)
+            .poll(&mut Context::from_waker(&waker))
+            .is_pending());
+        let token = fut.token;
+        drop(fut);
+        let reactor = Reactor::get().unwrap();
+        assert!(!reactor.wakers.lock().unwrap().contains_key(&token));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_fence_tokio() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
+            .unwrap();
+        let (fence, writer) = pipe_fence();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            signal(writer);
+        });
+        rt.block_on(fence.readable()).unwrap();
+    }
+}
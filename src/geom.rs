@@ -0,0 +1,235 @@
+//! 几何区域。
+//!
+use super::{RgaInfo, RgaPixelFormat};
+use std::io;
+
+/// 一个描述二维坐标点的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    /// 创建一个坐标点。
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+
+/// 一个描述矩形区域的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    /// 创建一个矩形区域。
+    pub const fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// 创建一个以原点为起点的矩形区域。
+    pub const fn with_size(width: i32, height: i32) -> Self {
+        Self::new(0, 0, width, height)
+    }
+
+    /// 返回右边界（不包含）。
+    pub const fn right(&self) -> i32 {
+        self.x + self.width
+    }
+
+    /// 返回下边界（不包含）。
+    pub const fn bottom(&self) -> i32 {
+        self.y + self.height
+    }
+
+    /// 当区域面积为 0 时返回 true。
+    pub const fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    /// 当 `other` 完全位于此区域内时返回 true。
+    pub const fn contains(&self, other: &Rect) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+
+    /// 返回与 `other` 的相交区域，不相交时返回 None。
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let r = Rect::new(
+            x,
+            y,
+            self.right().min(other.right()) - x,
+            self.bottom().min(other.bottom()) - y,
+        );
+        (!r.is_empty()).then_some(r)
+    }
+
+    /// 返回从此区域中去除 `other` 后剩余的部分，最多 4 个互不相交的区域。
+    pub fn subtract(&self, other: &Rect) -> Vec<Rect> {
+        let cut = match self.intersect(other) {
+            Some(cut) => cut,
+            None => return vec![*self],
+        };
+        let pieces = [
+            Rect::new(self.x, self.y, self.width, cut.y - self.y),
+            Rect::new(
+                self.x,
+                cut.bottom(),
+                self.width,
+                self.bottom() - cut.bottom(),
+            ),
+            Rect::new(self.x, cut.y, cut.x - self.x, cut.height),
+            Rect::new(cut.right(), cut.y, self.right() - cut.right(), cut.height),
+        ];
+        pieces.into_iter().filter(|r| !r.is_empty()).collect()
+    }
+
+    /// 返回平移后的区域。
+    pub const fn translate(&self, dx: i32, dy: i32) -> Rect {
+        Rect::new(self.x + dx, self.y + dy, self.width, self.height)
+    }
+
+    /// 按 YUV 色度采样间隔对齐区域：起点向下对齐，终点向上对齐，
+    /// 但不超过 `bounds` 中已对齐的部分。
+    pub(crate) fn align_to(&self, (ax, ay): (i32, i32), bounds: &Rect) -> Rect {
+        let x0 = self.x.div_euclid(ax) * ax;
+        let y0 = self.y.div_euclid(ay) * ay;
+        let x1 = (-((-self.right()).div_euclid(ax)) * ax).min(bounds.right() / ax * ax);
+        let y1 = (-((-self.bottom()).div_euclid(ay)) * ay).min(bounds.bottom() / ay * ay);
+        Rect::new(x0, y0, x1 - x0, y1 - y0)
+    }
+
+    /// 当区域满足 YUV 色度采样对齐时返回 true。
+    pub(crate) fn is_aligned_to(&self, (ax, ay): (i32, i32)) -> bool {
+        self.x % ax == 0 && self.y % ay == 0 && self.width % ax == 0 && self.height % ay == 0
+    }
+}
+
+/// 一个描述 YUV 图像区域未按色度采样对齐时处理策略的枚举。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RgaAlignPolicy {
+    /// 起点向下、终点向上对齐。
+    #[default]
+    Round = 0,
+    /// 返回 `InvalidInput` 错误。
+    Error = 1,
+}
+
+impl RgaAlignPolicy {
+    pub(crate) fn from_raw(val: u8) -> Self {
+        match val {
+            1 => Self::Error,
+            _ => Self::Round,
+        }
+    }
+
+    /// 按策略处理 `format` 图像上的区域 `region`。
+    pub(crate) fn apply(
+        self,
+        region: Rect,
+        format: RgaPixelFormat,
+        bounds: &Rect,
+    ) -> Result<Rect, io::Error> {
+        let align = match format.chroma_subsampling() {
+            Some(align) if align != (1, 1) => align,
+            _ => return Ok(region),
+        };
+        if region.is_aligned_to(align) {
+            return Ok(region);
+        }
+        match self {
+            Self::Round => {
+                let r = region.align_to(align, bounds);
+                if r.is_empty() {
+                    Err(invalid_input("region is empty after alignment"))
+                } else {
+                    Ok(r)
+                }
+            }
+            Self::Error => Err(invalid_input("region is not aligned to chroma subsampling")),
+        }
+    }
+}
+
+/// 返回 `info` 的图像边界，并校验其区域位于内存跨度之内。
+pub(crate) fn bounds_of(info: &RgaInfo) -> Result<Rect, io::Error> {
+    let r = &info.rect;
+    if r.width <= 0
+        || r.height <= 0
+        || r.xoffset < 0
+        || r.yoffset < 0
+        || r.xoffset + r.width > r.wstride
+        || r.yoffset + r.height > r.hstride
+    {
+        return Err(invalid_input("image rect exceeds the buffer stride"));
+    }
+    Ok(Rect::with_size(r.width, r.height))
+}
+
+/// 返回仅覆盖 `info` 图像中 `region` 部分的操作信息。
+pub(crate) fn with_region(info: &RgaInfo, region: &Rect) -> RgaInfo {
+    let mut info = *info;
+    info.rect.xoffset += region.x;
+    info.rect.yoffset += region.y;
+    info.rect.width = region.width;
+    info.rect.height = region.height;
+    info
+}
+
+pub(crate) fn invalid_input(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intersect() {
+        let a = Rect::new(0, 0, 100, 50);
+        assert_eq!(
+            a.intersect(&Rect::new(90, 40, 20, 20)),
+            Some(Rect::new(90, 40, 10, 10))
+        );
+        assert_eq!(a.intersect(&Rect::new(100, 0, 10, 10)), None);
+        assert_eq!(
+            a.intersect(&Rect::new(-5, -5, 10, 10)),
+            Some(Rect::new(0, 0, 5, 5))
+        );
+    }
+
+    #[test]
+    fn test_align_policy() {
+        let bounds = Rect::with_size(64, 64);
+        let fmt = RgaPixelFormat::YCbCr420sp;
+        let r = RgaAlignPolicy::Round.apply(Rect::new(3, 5, 9, 9), fmt, &bounds);
+        assert_eq!(r.unwrap(), Rect::new(2, 4, 10, 10));
+        let r = RgaAlignPolicy::Round.apply(Rect::new(61, 1, 3, 2), fmt, &bounds);
+        assert_eq!(r.unwrap(), Rect::new(60, 0, 4, 4));
+        let r = RgaAlignPolicy::Error.apply(Rect::new(3, 4, 8, 8), fmt, &bounds);
+        assert!(r.is_err());
+        let r = RgaAlignPolicy::Error.apply(Rect::new(2, 4, 8, 8), fmt, &bounds);
+        assert_eq!(r.unwrap(), Rect::new(2, 4, 8, 8));
+        let fmt = RgaPixelFormat::YCbCr422sp;
+        let r = RgaAlignPolicy::Round.apply(Rect::new(3, 3, 8, 3), fmt, &bounds);
+        assert_eq!(r.unwrap(), Rect::new(2, 3, 10, 3));
+        let fmt = RgaPixelFormat::Rgba8888;
+        let r = RgaAlignPolicy::Error.apply(Rect::new(3, 3, 3, 3), fmt, &bounds);
+        assert_eq!(r.unwrap(), Rect::new(3, 3, 3, 3));
+    }
+}
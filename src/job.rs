@@ -0,0 +1,260 @@
+//! 批量提交多个操作。
+//!
+//! librga 的 C 接口未提供任务分组（job）功能，因此任务中的操作按顺序逐个提交。
+//! 启用 `v1_4_0` 时，各操作以异步模式提交并依次等待前一操作的栅栏，
+//! 整个任务只在最后返回一个栅栏，调用线程不会被中间操作阻塞。
+//! 需要分块或多趟处理的缩放、旋转操作无法异步提交，此时先等待前一操作完成再同步执行。
+#[cfg(feature = "v1_4_0")]
+use super::RgaFence;
+use super::{Rga, RgaInfo, RgaOrientation};
+use std::io;
+
+/// 一个描述任务中单个操作的类型。
+enum JobOp<'a> {
+    Blit {
+        src: &'a RgaInfo,
+        dst: &'a RgaInfo,
+        extra_src: Option<&'a RgaInfo>,
+    },
+    Scale {
+        src: &'a RgaInfo,
+        dst: &'a RgaInfo,
+    },
+    Rotate {
+        src: &'a RgaInfo,
+        dst: &'a RgaInfo,
+        orient: RgaOrientation,
+    },
+    Fill {
+        dst: &'a RgaInfo,
+        color: u32,
+    },
+}
+
+/// 一个描述批量提交任务的类型，通过 [`Rga::job`] 创建。
+pub struct RgaJob<'a> {
+    rga: &'a Rga,
+    ops: Vec<JobOp<'a>>,
+}
+
+impl Rga {
+    /// 创建一个批量提交任务。
+    pub fn job(&self) -> RgaJob<'_> {
+        RgaJob {
+            rga: self,
+            ops: Vec::new(),
+        }
+    }
+}
+
+impl<'a> RgaJob<'a> {
+    /// 添加一个比特操作。
+    pub fn blit(
+        &mut self,
+        src: &'a RgaInfo,
+        dst: &'a RgaInfo,
+        extra_src: Option<&'a RgaInfo>,
+    ) -> &mut Self {
+        self.ops.push(JobOp::Blit {
+            src,
+            dst,
+            extra_src,
+        });
+        self
+    }
+
+    /// 添加一个颜色空间转换操作。
+    pub fn csc(&mut self, src: &'a RgaInfo, dst: &'a RgaInfo) -> &mut Self {
+        self.blit(src, dst, None)
+    }
+
+    /// 添加一个缩放操作，与 [`Rga::scale`] 相同，必要时分多趟或分块处理。
+    pub fn scale(&mut self, src: &'a RgaInfo, dst: &'a RgaInfo) -> &mut Self {
+        self.ops.push(JobOp::Scale { src, dst });
+        self
+    }
+
+    /// 添加一个旋转或翻转操作，与 [`Rga::rotate`] 相同，必要时分块处理。
+    pub fn rotate<T: Into<RgaOrientation>>(
+        &mut self,
+        src: &'a RgaInfo,
+        dst: &'a RgaInfo,
+        trans: T,
+    ) -> &mut Self {
+        self.ops.push(JobOp::Rotate {
+            src,
+            dst,
+            orient: trans.into(),
+        });
+        self
+    }
+
+    /// 添加一个颜色填充操作。
+    pub fn fill(&mut self, dst: &'a RgaInfo, color: u32) -> &mut Self {
+        self.ops.push(JobOp::Fill { dst, color });
+        self
+    }
+
+    /// 返回操作数量。
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// 当任务中没有操作时返回 true。
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// 同步提交所有操作，全部完成后返回。
+    ///
+    /// 遇到错误时停止提交，之前的操作已经执行。
+    pub fn submit(self) -> Result<(), io::Error> {
+        for op in &self.ops {
+            match op {
+                JobOp::Blit {
+                    src,
+                    dst,
+                    extra_src,
+                } => {
+                    let mut dst = **dst;
+                    let mut extra = extra_src.copied();
+                    self.rga.blit(src, &mut dst, extra.as_mut())?;
+                }
+                JobOp::Scale { src, dst } => self.rga.scale(src, &mut { **dst })?,
+                JobOp::Rotate { src, dst, orient } => {
+                    self.rga.rotate(src, &mut { **dst }, *orient)?
+                }
+                JobOp::Fill { dst, color } => self.rga.fill(dst, *color)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// 以异步模式提交所有操作，返回最后一个操作完成时触发的栅栏。
+    ///
+    /// 各操作依次等待前一操作的栅栏，第一个操作等待 `in_fence`。
+    /// 遇到提交错误时停止提交，之前的操作可能仍在执行。
+    #[cfg(feature = "v1_4_0")]
+    pub fn submit_fenced(self, in_fence: Option<&RgaFence>) -> Result<RgaFence, io::Error> {
+        let mut prev: Option<RgaFence> = None;
+        for op in &self.ops {
+            let wait = prev.as_ref().or(in_fence);
+            let fence = match op {
+                JobOp::Blit {
+                    src,
+                    dst,
+                    extra_src,
+                } => {
+                    let mut dst = **dst;
+                    let mut extra = extra_src.copied();
+                    self.rga.blit_fenced(src, &mut dst, extra.as_mut(), wait)?
+                }
+                JobOp::Scale { src, dst } => {
+                    let mut dst = **dst;
+                    if self.rga.needs_multipass(src, &dst) || self.rga.needs_tiling(src, &dst) {
+                        run_after(wait, || self.rga.scale(src, &mut dst))?
+                    } else {
+                        self.rga.check_scale_mode(src)?;
+                        self.rga.blit_fenced(src, &mut dst, None, wait)?
+                    }
+                }
+                JobOp::Rotate { src, dst, orient } => {
+                    let mut dst = **dst;
+                    if self.rga.needs_tiling(src, &dst) {
+                        run_after(wait, || self.rga.rotate(src, &mut dst, *orient))?
+                    } else {
+                        let src = RgaInfo {
+                            rotation: orient.to_raw(),
+                            ..**src
+                        };
+                        self.rga.blit_fenced(&src, &mut dst, None, wait)?
+                    }
+                }
+                JobOp::Fill { dst, color } => self.rga.fill_fenced(dst, *color, wait)?,
+            };
+            prev = Some(fence);
+        }
+        match (prev, in_fence) {
+            (Some(fence), _) => Ok(fence),
+            (None, Some(fence)) => fence.try_clone(),
+            (None, None) => Ok(RgaFence::signaled()),
+        }
+    }
+
+    /// 以异步模式提交所有操作，返回在全部完成时就绪的 `Future`。
+    #[cfg(feature = "async")]
+    pub fn submit_async(self) -> super::RgaFenceFuture {
+        match self.submit_fenced(None) {
+            Ok(fence) => fence.into(),
+            Err(err) => super::RgaFenceFuture::failed(err),
+        }
+    }
+}
+
+/// 等待 `wait` 触发后同步执行 `op`，返回已触发的栅栏。
+#[cfg(feature = "v1_4_0")]
+fn run_after(
+    wait: Option<&RgaFence>,
+    op: impl FnOnce() -> Result<(), io::Error>,
+) -> Result<RgaFence, io::Error> {
+    if let Some(fence) = wait {
+        fence.wait(None)?;
+    }
+    op().map(|_| RgaFence::signaled())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RgaBuffer, RgaInfoBuilder, RgaPixelFormat, RgaRectBuilder};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_job_submit() {
+        let rga = Arc::new(Rga::new().unwrap());
+        let rect = RgaRectBuilder::new()
+            .size(64, 64)
+            .format(RgaPixelFormat::Rgba8888)
+            .build();
+        let small = RgaRectBuilder::new()
+            .size(32, 32)
+            .format(RgaPixelFormat::Rgba8888)
+            .build();
+        let bo_a = RgaBuffer::with_rect(Arc::clone(&rga), &rect).unwrap();
+        let bo_b = RgaBuffer::with_rect(Arc::clone(&rga), &small).unwrap();
+        let a = *RgaInfoBuilder::new().bo(&bo_a).rect(&rect).build();
+        let b = *RgaInfoBuilder::new().bo(&bo_b).rect(&small).build();
+        let mut job = rga.job();
+        assert!(job.is_empty());
+        job.fill(&a, 0xff00_00ff)
+            .scale(&a, &b)
+            .rotate(&b, &a, RgaOrientation::ROT_180);
+        assert_eq!(job.len(), 3);
+        job.submit().unwrap();
+
+        // 缩放与 `Rga::scale` 一样检查插值方式。
+        let bad = RgaInfo {
+            scale_mode: 0x7f,
+            ..a
+        };
+        let mut job = rga.job();
+        job.scale(&bad, &b);
+        assert!(job.submit().is_err());
+
+        #[cfg(feature = "v1_4_0")]
+        {
+            let mut job = rga.job();
+            job.csc(&a, &b).scale(&b, &a).fill(&b, 0);
+            let fence = job.submit_fenced(None).unwrap();
+            assert!(fence.wait(None).unwrap());
+            assert!(fence.is_signaled().unwrap());
+
+            // 无效的优先级使提交在该操作处失败。
+            let bad = RgaInfo { priority: 7, ..b };
+            let mut job = rga.job();
+            job.fill(&a, 0).fill(&bad, 0);
+            let err = job.submit_fenced(None).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
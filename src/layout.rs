@@ -0,0 +1,186 @@
+//! 图像内存布局。
+//!
+use super::geom::invalid_input;
+use super::{RgaPixelFormat, RgaRect};
+use std::io;
+
+/// AFBC 头部的对齐字节数。
+const AFBC_HEADER_ALIGN: usize = 4096;
+
+/// 一个描述图像内存布局的枚举，取值与 `rd_mode` 一致。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum MemoryLayout {
+    /// 逐行存储。
+    #[default]
+    Raster = 1 << 0,
+    /// AFBC 压缩，超级块大小为 16x16。
+    Afbc16x16 = 1 << 1,
+    /// 8x8 分块存储。
+    Tile8x8 = 1 << 2,
+}
+
+impl MemoryLayout {
+    /// 从 `rd_mode` 的取值转换，0 视为 [`Raster`]，无效时返回 None。
+    ///
+    /// [`Raster`]: MemoryLayout::Raster
+    pub fn from_raw(rd_mode: i32) -> Option<Self> {
+        match rd_mode {
+            0 | 1 => Some(Self::Raster),
+            2 => Some(Self::Afbc16x16),
+            4 => Some(Self::Tile8x8),
+            _ => None,
+        }
+    }
+
+    /// 返回 `rd_mode` 的取值。
+    pub fn rd_mode(self) -> i32 {
+        self as i32
+    }
+
+    /// 返回内存跨度及偏移需满足的对齐要求。
+    pub fn alignment(self) -> (i32, i32) {
+        match self {
+            Self::Raster => (1, 1),
+            Self::Afbc16x16 => (16, 16),
+            Self::Tile8x8 => (8, 8),
+        }
+    }
+
+    /// 当布局支持此像素格式时返回 true。
+    pub fn supports_format(self, fmt: RgaPixelFormat) -> bool {
+        use RgaPixelFormat::*;
+        match self {
+            Self::Raster => true,
+            Self::Afbc16x16 => matches!(
+                fmt,
+                Rgba8888
+                    | Rgbx8888
+                    | Bgra8888
+                    | Bgrx8888
+                    | Rgb888
+                    | Bgr888
+                    | Rgb565
+                    | YCbCr420sp
+                    | YCrCb420sp
+                    | YCbCr422sp
+                    | YCrCb422sp
+            ),
+            Self::Tile8x8 => matches!(
+                fmt,
+                YCbCr420sp | YCrCb420sp | YCbCr422sp | YCrCb422sp | YCbCr400
+            ),
+        }
+    }
+
+    /// 返回 `width` x `height` 图像的头部字节数，仅 AFBC 布局有头部。
+    ///
+    /// 每个 16x16 超级块对应 16 字节头部，整体按 4096 字节对齐。
+    pub fn header_size(self, width: i32, height: i32) -> usize {
+        match self {
+            Self::Afbc16x16 => {
+                let (w, h) = self.aligned_size(width, height);
+                let size = w * h / 16;
+                size.div_ceil(AFBC_HEADER_ALIGN) * AFBC_HEADER_ALIGN
+            }
+            _ => 0,
+        }
+    }
+
+    /// 返回 `width` x `height` 图像的数据字节数，AFBC 布局按未压缩的最大值计算。
+    pub fn payload_size(self, width: i32, height: i32, fmt: RgaPixelFormat) -> usize {
+        let (w, h) = self.aligned_size(width, height);
+        w * h * fmt.bits_per_pixel() / 8
+    }
+
+    /// 返回 `width` x `height` 图像所需的缓冲区字节数。
+    pub fn buffer_size(self, width: i32, height: i32, fmt: RgaPixelFormat) -> usize {
+        self.header_size(width, height) + self.payload_size(width, height, fmt)
+    }
+
+    /// 校验图像区域是否满足此布局的格式及对齐要求。
+    pub fn validate(self, rect: &RgaRect) -> Result<(), io::Error> {
+        if self == Self::Raster {
+            return Ok(());
+        }
+        let fmt = RgaPixelFormat::from_raw(rect.format);
+        if !self.supports_format(fmt) {
+            return Err(invalid_input(&format!(
+                "format {:?} is not supported by {:?} layout",
+                fmt, self
+            )));
+        }
+        let (ax, ay) = self.alignment();
+        if rect.wstride % ax != 0
+            || rect.hstride % ay != 0
+            || rect.xoffset % ax != 0
+            || rect.yoffset % ay != 0
+        {
+            return Err(invalid_input(&format!(
+                "stride and offset must be aligned to {}x{} for {:?} layout",
+                ax, ay, self
+            )));
+        }
+        Ok(())
+    }
+
+    fn aligned_size(self, width: i32, height: i32) -> (usize, usize) {
+        let (ax, ay) = self.alignment();
+        let w = (width + ax - 1) / ax * ax;
+        let h = (height + ay - 1) / ay * ay;
+        (w.max(0) as usize, h.max(0) as usize)
+    }
+}
+
+#[cfg(feature = "v1_4_0")]
+impl super::Rga {
+    /// 检查操作信息中的内存布局是否有效。
+    pub(crate) fn check_layout(&self, info: &super::RgaInfo) -> Result<(), io::Error> {
+        match MemoryLayout::from_raw(info.rd_mode) {
+            Some(layout) => layout.validate(&info.rect),
+            None => Err(invalid_input("invalid read mode")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RgaRectBuilder;
+
+    #[test]
+    fn test_layout_sizes() {
+        let fmt = RgaPixelFormat::Rgba8888;
+        let afbc = MemoryLayout::Afbc16x16;
+        assert_eq!(afbc.header_size(1920, 1080), 131072);
+        assert_eq!(afbc.payload_size(1920, 1080, fmt), 1920 * 1088 * 4);
+        assert_eq!(afbc.buffer_size(1920, 1080, fmt), 131072 + 1920 * 1088 * 4);
+        let raster = MemoryLayout::Raster;
+        assert_eq!(raster.buffer_size(1920, 1080, fmt), 1920 * 1080 * 4);
+        let nv12 = RgaPixelFormat::YCbCr420sp;
+        assert_eq!(
+            MemoryLayout::Tile8x8.buffer_size(100, 50, nv12),
+            104 * 56 * 3 / 2
+        );
+        assert_eq!(MemoryLayout::from_raw(0), Some(MemoryLayout::Raster));
+        assert_eq!(MemoryLayout::from_raw(3), None);
+    }
+
+    #[test]
+    fn test_layout_validate() {
+        let rect = RgaRectBuilder::new()
+            .size(1920, 1080)
+            .format(RgaPixelFormat::Rgba8888)
+            .layout(MemoryLayout::Afbc16x16)
+            .build();
+        assert_eq!((rect.wstride, rect.hstride), (1920, 1088));
+        assert!(MemoryLayout::Afbc16x16.validate(&rect).is_ok());
+        assert!(MemoryLayout::Tile8x8.validate(&rect).is_err());
+        let odd = RgaRectBuilder::new()
+            .size(1920, 1080)
+            .format(RgaPixelFormat::Rgba8888)
+            .build();
+        assert!(MemoryLayout::Raster.validate(&odd).is_ok());
+        assert!(MemoryLayout::Afbc16x16.validate(&odd).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! 保持宽高比缩放（信箱填充）。
+//!
+use super::geom::{bounds_of, with_region};
+use super::{Rect, Rga, RgaInfo, RgaPixelFormat};
+use std::io;
+
+/// 一个描述缩放后图像在目标图像中对齐方式的枚举。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    /// 居中放置。
+    #[default]
+    Center,
+    /// 放置在左上角。
+    TopLeft,
+}
+
+/// 一个描述信箱缩放结果的类型，可用于将目标图像坐标映射回源图像。
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Letterbox {
+    /// 水平缩放比例。
+    pub scale_x: f32,
+    /// 垂直缩放比例。
+    pub scale_y: f32,
+    /// 缩放后图像在目标图像中的水平偏移。
+    pub offset_x: i32,
+    /// 缩放后图像在目标图像中的垂直偏移。
+    pub offset_y: i32,
+    /// 缩放后图像的宽度。
+    pub width: i32,
+    /// 缩放后图像的高度。
+    pub height: i32,
+}
+
+impl Letterbox {
+    /// 计算 `src` 大小的图像放入 `dst` 大小的图像时的布局，
+    /// `(ax, ay)` 为目标格式要求的对齐间隔。
+    pub fn plan(src: (i32, i32), dst: (i32, i32), align: Align, (ax, ay): (i32, i32)) -> Self {
+        let (sw, sh) = src;
+        let (dw, dh) = dst;
+        let scale = (dw as f64 / sw as f64).min(dh as f64 / sh as f64);
+        let fit = |s: i32, d: i32, a: i32| {
+            let v = ((s as f64 * scale).round() as i32).min(d);
+            (v / a * a).max(a)
+        };
+        let width = fit(sw, dw, ax);
+        let height = fit(sh, dh, ay);
+        let (offset_x, offset_y) = match align {
+            Align::Center => ((dw - width) / 2 / ax * ax, (dh - height) / 2 / ay * ay),
+            Align::TopLeft => (0, 0),
+        };
+        Self {
+            scale_x: width as f32 / sw as f32,
+            scale_y: height as f32 / sh as f32,
+            offset_x,
+            offset_y,
+            width,
+            height,
+        }
+    }
+
+    /// 返回缩放后图像在目标图像中的区域。
+    pub fn content(&self) -> Rect {
+        Rect::new(self.offset_x, self.offset_y, self.width, self.height)
+    }
+
+    /// 返回目标图像中需要填充的区域，最多 4 个。
+    pub fn padding(&self, dst: (i32, i32)) -> Vec<Rect> {
+        let (dw, dh) = dst;
+        let c = self.content();
+        [
+            Rect::new(0, 0, dw, c.y),
+            Rect::new(0, c.bottom(), dw, dh - c.bottom()),
+            Rect::new(0, c.y, c.x, c.height),
+            Rect::new(c.right(), c.y, dw - c.right(), c.height),
+        ]
+        .into_iter()
+        .filter(|r| !r.is_empty())
+        .collect()
+    }
+
+    /// 将目标图像中的坐标映射回源图像。
+    pub fn to_source(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            (x - self.offset_x as f32) / self.scale_x,
+            (y - self.offset_y as f32) / self.scale_y,
+        )
+    }
+
+    /// 将源图像中的坐标映射到目标图像。
+    pub fn to_target(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            x * self.scale_x + self.offset_x as f32,
+            y * self.scale_y + self.offset_y as f32,
+        )
+    }
+}
+
+impl Rga {
+    /// 保持宽高比将源图像缩放到目标图像中，剩余部分使用 `pad_color` 填充。
+    ///
+    /// 仅填充缩放后图像之外的区域，宽高比一致时只需一次缩放操作。
+    pub fn letterbox(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        pad_color: u32,
+        align: Align,
+    ) -> Result<Letterbox, io::Error> {
+        let src_bounds = bounds_of(src)?;
+        let dst_bounds = bounds_of(dst)?;
+        let subsampling = RgaPixelFormat::from_raw(dst.rect.format)
+            .chroma_subsampling()
+            .unwrap_or((1, 1));
+        let lb = Letterbox::plan(
+            (src_bounds.width, src_bounds.height),
+            (dst_bounds.width, dst_bounds.height),
+            align,
+            subsampling,
+        );
+        for pad in lb.padding((dst_bounds.width, dst_bounds.height)) {
+            self.fill(&with_region(dst, &pad), pad_color)?;
+        }
+        let mut content = with_region(dst, &lb.content());
+        self.scale(src, &mut content)?;
+        Ok(lb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_letterbox_plan() {
+        let lb = Letterbox::plan((1920, 1080), (640, 640), Align::Center, (1, 1));
+        assert_eq!(lb.content(), Rect::new(0, 140, 640, 360));
+        assert_eq!(
+            lb.padding((640, 640)),
+            vec![Rect::new(0, 0, 640, 140), Rect::new(0, 500, 640, 140)]
+        );
+        let (x, y) = lb.to_source(320.0, 320.0);
+        assert!((x - 960.0).abs() < 1e-3 && (y - 540.0).abs() < 1e-3);
+        let (x, y) = lb.to_target(1920.0, 1080.0);
+        assert!((x - 640.0).abs() < 1e-3 && (y - 500.0).abs() < 1e-3);
+
+        let lb = Letterbox::plan((1080, 1920), (640, 640), Align::TopLeft, (1, 1));
+        assert_eq!(lb.content(), Rect::new(0, 0, 360, 640));
+        assert_eq!(lb.padding((640, 640)), vec![Rect::new(360, 0, 280, 640)]);
+
+        let lb = Letterbox::plan((1280, 720), (640, 360), Align::Center, (1, 1));
+        assert!(lb.padding((640, 360)).is_empty());
+    }
+
+    #[test]
+    fn test_letterbox_plan_yuv() {
+        let lb = Letterbox::plan((1000, 333), (416, 416), Align::Center, (2, 2));
+        let c = lb.content();
+        assert_eq!((c.x % 2, c.y % 2, c.width % 2, c.height % 2), (0, 0, 0, 0));
+        assert_eq!(c.width, 416);
+        let area: i32 = lb
+            .padding((416, 416))
+            .iter()
+            .map(|r| r.width * r.height)
+            .sum();
+        assert_eq!(area + c.width * c.height, 416 * 416);
+    }
+}
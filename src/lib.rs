@@ -28,8 +28,11 @@
 //! // 执行旋转操作
 //! rga.rotate(&src_info, &mut dst_info, RgaTransform::Rot90).unwrap();
 //! ```
+use bounce::BouncePool;
+use scratch::ScratchPool;
 use std::io;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, OnceLock};
 
 pub use rkrga_sys as ffi;
 
@@ -38,19 +41,41 @@ pub type RgaRect = ffi::rga_rect_t;
 
 /// 一个描述 RGA 模块的类型。
 #[derive(Debug)]
-pub struct Rga;
+pub struct Rga {
+    align_policy: AtomicU8,
+    auto_align: AtomicBool,
+    bounce: BouncePool,
+    caps: OnceLock<RgaCapabilities>,
+    scratch: ScratchPool,
+}
 
 impl Rga {
     /// 创建一个 RGA 对象实例。
     pub fn new() -> Result<Self, io::Error> {
         unsafe {
             match ffi::c_RkRgaInit() {
-                0 => Ok(Self),
+                0 => Ok(Self {
+                    align_policy: AtomicU8::new(RgaAlignPolicy::default() as u8),
+                    auto_align: AtomicBool::new(false),
+                    bounce: BouncePool::default(),
+                    caps: OnceLock::new(),
+                    scratch: ScratchPool::default(),
+                }),
                 err => Err(io::Error::from_raw_os_error(err)),
             }
         }
     }
 
+    /// 返回 YUV 图像区域未按色度采样对齐时的处理策略。
+    pub fn align_policy(&self) -> RgaAlignPolicy {
+        RgaAlignPolicy::from_raw(self.align_policy.load(Ordering::Relaxed))
+    }
+
+    /// 设置 YUV 图像区域未按色度采样对齐时的处理策略。
+    pub fn set_align_policy(&self, policy: RgaAlignPolicy) {
+        self.align_policy.store(policy as u8, Ordering::Relaxed);
+    }
+
     /// 分配一个内存缓冲对象。
     pub fn alloc_buffer(
         self: &Arc<Self>,
@@ -67,6 +92,25 @@ impl Rga {
         src: &RgaInfo,
         dst: &mut RgaInfo,
         extra_src: Option<&mut RgaInfo>,
+    ) -> Result<(), io::Error> {
+        #[cfg(feature = "v1_4_0")]
+        {
+            self.check_schedule(src)?;
+            self.check_schedule(dst)?;
+            self.check_layout(src)?;
+            self.check_layout(dst)?;
+        }
+        if self.auto_align() {
+            return self.blit_bounced(src, dst, extra_src);
+        }
+        self.blit_raw(src, dst, extra_src)
+    }
+
+    fn blit_raw(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        extra_src: Option<&mut RgaInfo>,
     ) -> Result<(), io::Error> {
         unsafe {
             let mut src = *src;
@@ -80,13 +124,28 @@ impl Rga {
         }
     }
 
-    /// 对象图像进行色彩空间转换操作。
+    /// 对象图像进行色彩空间转换操作，超出硬件尺寸限制时分块处理。
     pub fn csc(&self, src: &RgaInfo, dst: &mut RgaInfo) -> Result<(), io::Error> {
+        if self.needs_tiling(src, dst) {
+            return self.scale_tiled(src, dst);
+        }
         self.blit(src, dst, None)
     }
 
-    /// 使用指定颜色填充图像。
+    /// 使用指定颜色填充图像，超出硬件尺寸限制时分块处理。
     pub fn fill(&self, dst: &RgaInfo, color: u32) -> Result<(), io::Error> {
+        if self.needs_tiling(dst, dst) {
+            return self.fill_tiled(dst, color);
+        }
+        #[cfg(feature = "v1_4_0")]
+        self.check_schedule(dst)?;
+        if self.auto_align() {
+            return self.fill_bounced(dst, color);
+        }
+        self.fill_raw(dst, color)
+    }
+
+    fn fill_raw(&self, dst: &RgaInfo, color: u32) -> Result<(), io::Error> {
         unsafe {
             let mut dst = RgaInfo {
                 color: color as i32,
@@ -99,24 +158,51 @@ impl Rga {
         }
     }
 
-    /// 对象图像进行旋转操作。
-    pub fn rotate(
+    /// 对象图像进行旋转或翻转操作，超出硬件尺寸限制时分块处理。
+    pub fn rotate<T: Into<RgaOrientation>>(
         &self,
         src: &RgaInfo,
         dst: &mut RgaInfo,
-        trans: RgaTransform,
+        trans: T,
     ) -> Result<(), io::Error> {
+        let orient = trans.into();
+        if self.needs_tiling(src, dst) {
+            return self.rotate_tiled(src, dst, orient);
+        }
         let src = RgaInfo {
-            rotation: trans as i32,
+            rotation: orient.to_raw(),
             ..*src
         };
         self.blit(&src, dst, None)
     }
 
-    /// 对象图像进行缩放操作。
+    /// 对象图像进行缩放操作，插值方式由 `src` 的 `scale_mode` 指定。
+    ///
+    /// 缩放倍数超出硬件限制时分多趟缩放，图像超出硬件尺寸限制时分块处理。
     pub fn scale(&self, src: &RgaInfo, dst: &mut RgaInfo) -> Result<(), io::Error> {
+        self.check_scale_mode(src)?;
+        if self.needs_multipass(src, dst) {
+            return self.scale_multipass(src, dst);
+        }
+        if self.needs_tiling(src, dst) {
+            return self.scale_tiled(src, dst);
+        }
         self.blit(src, dst, None)
     }
+
+    /// 对象图像进行光栅操作。
+    pub fn rop<R: Into<RgaRop3>>(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        code: R,
+    ) -> Result<(), io::Error> {
+        let src = RgaInfo {
+            rop_code: code.into().code() as i32,
+            ..*src
+        };
+        self.blit(&src, dst, None)
+    }
 }
 
 impl Default for Rga {
@@ -127,6 +213,9 @@ impl Default for Rga {
 
 impl Drop for Rga {
     fn drop(&mut self) {
+        // 缓存的缓冲对象须在反初始化之前释放。
+        self.bounce.clear();
+        self.scratch.clear();
         unsafe {
             ffi::c_RkRgaDeInit();
         }
@@ -136,7 +225,7 @@ impl Drop for Rga {
 /// 一个描述 RGA 颜色空间模式的枚举。
 #[repr(i32)]
 pub enum RgaColorSpaceMode {
-    /// YUV -> RGB BT.601 MPEG
+    /// YUV -> RGB BT.601 MPEG，RGB -> YUV 时为 BT.601 有限范围
     Yuv2RgbMode0 = 0x0000,
     /// YUV -> RGB BT.601 JPEG
     Yuv2RgbMode1 = 0x0001,
@@ -191,10 +280,70 @@ pub enum RgaTransform {
     Rot270 = ffi::HAL_TRANSFORM_ROT_270 as i32,
 }
 
+mod alpha;
 mod bo;
+mod bounce;
 mod builder;
+mod caps;
+mod colorimetry;
+mod crop;
+mod csc;
+mod dither;
+mod draw;
+mod fade;
+#[cfg(feature = "v1_4_0")]
+mod fence;
+mod fill;
+#[cfg(feature = "async")]
+mod future;
+mod geom;
+mod job;
+mod layout;
+mod letterbox;
+mod mosaic;
+mod multipass;
+mod nn;
+mod orient;
+#[cfg(feature = "v1_7_2")]
+mod osd;
+mod pipeline;
 mod pixfmt;
+mod rop;
+mod scale;
+mod sched;
+mod scratch;
+#[cfg(feature = "v1_7_2")]
+mod slice;
+pub mod soft;
+mod tile;
 
 pub use bo::*;
+pub use bounce::needs_bounce;
 pub use builder::*;
+pub use caps::*;
+pub use colorimetry::*;
+pub use csc::*;
+pub use dither::*;
+pub use fade::*;
+#[cfg(feature = "v1_4_0")]
+pub use fence::*;
+#[cfg(feature = "async")]
+pub use future::*;
+pub use geom::{Point, Rect, RgaAlignPolicy};
+pub use job::*;
+pub use layout::*;
+pub use letterbox::*;
+pub use mosaic::*;
+pub use multipass::*;
+pub use nn::*;
+pub use orient::*;
+#[cfg(feature = "v1_7_2")]
+pub use osd::*;
+pub use pipeline::*;
 pub use pixfmt::*;
+pub use rop::*;
+pub use scale::*;
+pub use sched::*;
+#[cfg(feature = "v1_7_2")]
+pub use slice::*;
+pub use tile::*;
@@ -0,0 +1,121 @@
+//! 马赛克（像素化）区域。
+//!
+use super::geom::{bounds_of, invalid_input};
+use super::{soft, Rect, Rga, RgaInfo, RgaPixelFormat};
+use std::io;
+
+/// 一个描述硬件马赛克块大小的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MosaicBlock {
+    /// 8x8 像素。
+    Block8 = 0,
+    /// 16x16 像素。
+    Block16 = 1,
+    /// 32x32 像素。
+    Block32 = 2,
+    /// 64x64 像素。
+    Block64 = 3,
+    /// 128x128 像素。
+    Block128 = 4,
+}
+
+impl MosaicBlock {
+    /// 返回与块大小对应的硬件模式，硬件不支持时返回 None。
+    pub fn from_size(size: u32) -> Option<Self> {
+        match size {
+            8 => Some(Self::Block8),
+            16 => Some(Self::Block16),
+            32 => Some(Self::Block32),
+            64 => Some(Self::Block64),
+            128 => Some(Self::Block128),
+            _ => None,
+        }
+    }
+
+    /// 返回块的边长。
+    pub fn size(self) -> u32 {
+        8 << self as u32
+    }
+}
+
+impl Rga {
+    /// 对目标图像中的多个区域原地进行马赛克处理。
+    ///
+    /// 块大小为 8、16、32、64、128 之一时使用硬件处理，否则或硬件处理失败时，
+    /// 若图像已映射到用户空间则回退到 CPU 实现。
+    pub fn mosaic(
+        &self,
+        dst: &mut RgaInfo,
+        regions: &[Rect],
+        block_size: u32,
+    ) -> Result<(), io::Error> {
+        if block_size == 0 || block_size > i32::MAX as u32 {
+            return Err(invalid_input("mosaic block size out of range"));
+        }
+        let bounds = bounds_of(dst)?;
+        let fmt = RgaPixelFormat::from_raw(dst.rect.format);
+        for region in regions {
+            let region = match region.intersect(&bounds) {
+                Some(r) => self.align_policy().apply(r, fmt, &bounds)?,
+                None => continue,
+            };
+            let hw = match MosaicBlock::from_size(block_size) {
+                Some(block) => self.mosaic_hw(dst, &region, block),
+                None => Err(io::Error::from(io::ErrorKind::Unsupported)),
+            };
+            if let Err(err) = hw {
+                if !soft::mosaic_supported(fmt) {
+                    return Err(err);
+                }
+                match unsafe { soft::info_bytes_mut(dst) } {
+                    Some(buf) => soft::mosaic(buf, &dst.rect, &region, block_size as i32)?,
+                    None => return Err(err),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "v1_7_2")]
+    fn mosaic_hw(&self, dst: &RgaInfo, region: &Rect, block: MosaicBlock) -> Result<(), io::Error> {
+        let src = super::geom::with_region(dst, region);
+        let mut dst = src;
+        dst.mosaic_info.enable = 1;
+        dst.mosaic_info.mode = block as u8;
+        self.blit(&src, &mut dst, None)
+    }
+
+    #[cfg(not(feature = "v1_7_2"))]
+    fn mosaic_hw(
+        &self,
+        _dst: &RgaInfo,
+        _region: &Rect,
+        _block: MosaicBlock,
+    ) -> Result<(), io::Error> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::soft::SoftImage;
+
+    #[test]
+    fn test_mosaic_block_size() {
+        let rga = Rga::new().unwrap();
+        let mut img = SoftImage::new(8, 8, RgaPixelFormat::Rgba8888);
+        let mut dst = RgaInfo {
+            fd: -1,
+            virAddr: img.data.as_mut_ptr() as *mut _,
+            rect: img.rect,
+            format: img.rect.format,
+            ..Default::default()
+        };
+        for block in [0, u32::MAX] {
+            let err = rga.mosaic(&mut dst, &[Rect::new(0, 0, 8, 8)], block);
+            assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
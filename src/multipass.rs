@@ -0,0 +1,198 @@
+//! 超出硬件缩放倍数限制时的多趟缩放。
+//!
+//! 缩放倍数超出限制时，按几何级数在源、目标大小之间规划若干中间分辨率，
+//! 中间结果保存在 [`Rga`] 缓存的临时缓冲区中，格式与目标图像一致。
+use super::geom::bounds_of;
+use super::{ffi, CoreMask, Rga, RgaInfo, RgaPixelFormat, RgaRectBuilder, ScaleFilter};
+use std::io;
+
+/// 规划的最大趟数。
+const MAX_PASSES: u32 = 8;
+
+/// 当 `s` 到 `d` 的缩放在 `(down, up)` 倍以内时返回 true。
+fn within(s: i32, d: i32, (down, up): (u32, u32)) -> bool {
+    let (s, d) = (s as i64, d as i64);
+    d <= s * up as i64 && d * down as i64 >= s
+}
+
+/// 返回一维缩放所需的最少趟数。
+fn axis_passes(s: i32, d: i32, (down, up): (u32, u32)) -> u32 {
+    let (ratio, limit) = if d >= s {
+        (d as f64 / s as f64, up as f64)
+    } else {
+        (s as f64 / d as f64, down as f64)
+    };
+    let mut n = 1;
+    while limit.powi(n as i32) < ratio && n < MAX_PASSES {
+        n += 1;
+    }
+    n
+}
+
+/// 规划将 `src` 大小的图像缩放到 `dst` 大小时每一趟的输出大小，最后一项为 `dst`。
+///
+/// 中间大小按 `align` 对齐，在 [`MAX_PASSES`] 趟内无法完成时返回 None。
+pub fn plan_scale_passes(
+    src: (i32, i32),
+    dst: (i32, i32),
+    limit: (u32, u32),
+    align: i32,
+) -> Option<Vec<(i32, i32)>> {
+    let limit = (limit.0.max(1), limit.1.max(1));
+    let align = align.max(1);
+    let first = axis_passes(src.0, dst.0, limit).max(axis_passes(src.1, dst.1, limit));
+    let lerp = |s: i32, d: i32, k: u32, n: u32| {
+        let v = s as f64 * (d as f64 / s as f64).powf(k as f64 / n as f64);
+        ((v / align as f64).round() as i32 * align).max(align)
+    };
+    (first..=MAX_PASSES).find_map(|n| {
+        let sizes: Vec<_> = (1..=n)
+            .map(|k| match k {
+                k if k == n => dst,
+                k => (lerp(src.0, dst.0, k, n), lerp(src.1, dst.1, k, n)),
+            })
+            .collect();
+        let mut prev = src;
+        for &size in &sizes {
+            if !within(prev.0, size.0, limit) || !within(prev.1, size.1, limit) {
+                return None;
+            }
+            prev = size;
+        }
+        Some(sizes)
+    })
+}
+
+impl Rga {
+    /// 返回适用于 `src`、`dst` 两幅图像的缩放倍数限制。
+    pub(crate) fn scale_limit(&self, src: &RgaInfo, dst: &RgaInfo) -> (u32, u32) {
+        let caps = self.capabilities();
+        let (a, b) = (
+            caps.scale_limit_on(CoreMask::of(src)),
+            caps.scale_limit_on(CoreMask::of(dst)),
+        );
+        (a.0.min(b.0), a.1.min(b.1))
+    }
+
+    /// 当 `src` 到 `dst` 的缩放超出硬件倍数限制时返回 true。
+    pub(crate) fn needs_multipass(&self, src: &RgaInfo, dst: &RgaInfo) -> bool {
+        let limit = self.scale_limit(src, dst);
+        let (s, d) = (&src.rect, &dst.rect);
+        if s.width <= 0 || s.height <= 0 || d.width <= 0 || d.height <= 0 {
+            return false;
+        }
+        !within(s.width, d.width, limit) || !within(s.height, d.height, limit)
+    }
+
+    /// 分多趟对象图像进行缩放操作。
+    ///
+    /// 中间各趟缩小时使用双线性插值，放大时优先使用双三次插值；
+    /// 调用者指定最近邻插值时各趟均使用最近邻插值，最后一趟总是使用调用者指定的插值方式。
+    /// 各趟均同步执行，`dst` 的异步模式不生效。
+    pub(crate) fn scale_multipass(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+    ) -> Result<(), io::Error> {
+        let (s, d) = (bounds_of(src)?, bounds_of(dst)?);
+        let fmt = RgaPixelFormat::from_raw(dst.rect.format);
+        let align = if fmt.is_yuv() { 2 } else { 1 };
+        let passes = plan_scale_passes(
+            (s.width, s.height),
+            (d.width, d.height),
+            self.scale_limit(src, dst),
+            align,
+        )
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "could not scale {}x{} to {}x{} within {} passes",
+                    s.width, s.height, d.width, d.height, MAX_PASSES
+                ),
+            )
+        })?;
+        let filter = ScaleFilter::from_raw(src.scale_mode).unwrap_or_default();
+        let bicubic = self
+            .capabilities()
+            .supports_filter_on(ScaleFilter::Bicubic, CoreMask::of(dst));
+
+        let mut input = *src;
+        let mut prev = (s.width, s.height);
+        // 上一趟的输出需保留到本趟读取完毕。
+        let mut held = None;
+        for &size in &passes[..passes.len() - 1] {
+            input.scale_mode = match filter {
+                ScaleFilter::Nearest => ScaleFilter::Nearest,
+                _ if size.0 > prev.0 && bicubic => ScaleFilter::Bicubic,
+                _ => ScaleFilter::Bilinear,
+            } as i32;
+            let rect = RgaRectBuilder::new()
+                .size(size.0, size.1)
+                .format(fmt)
+                .build();
+            let mut buf = self.scratch.take(&rect)?;
+            let mut out = buf.info(rect, dst);
+            self.scale(&input, &mut out)?;
+            input = out;
+            prev = size;
+            held = Some(buf);
+        }
+        input.scale_mode = filter as i32;
+        // 中间结果返回后即归还缓存，最后一趟须在硬件读取完毕后才返回。
+        let mut out = *dst;
+        out.sync_mode = ffi::RGA_BLIT_SYNC as i32;
+        let result = self.scale(&input, &mut out);
+        drop(held);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(src: (i32, i32), dst: (i32, i32), limit: (u32, u32), align: i32) -> usize {
+        let passes = plan_scale_passes(src, dst, limit, align).unwrap();
+        assert_eq!(*passes.last().unwrap(), dst);
+        let mut prev = src;
+        for &size in &passes {
+            assert!(within(prev.0, size.0, limit), "{:?} -> {:?}", prev, size);
+            assert!(within(prev.1, size.1, limit), "{:?} -> {:?}", prev, size);
+            if size != dst {
+                assert_eq!((size.0 % align, size.1 % align), (0, 0));
+            }
+            prev = size;
+        }
+        passes.len()
+    }
+
+    #[test]
+    fn test_plan_scale_passes() {
+        assert_eq!(check((1920, 1080), (1280, 720), (16, 16), 1), 1);
+        assert_eq!(check((3840, 2160), (64, 36), (16, 16), 1), 2);
+        assert_eq!(check((3840, 2160), (64, 36), (8, 8), 2), 2);
+        assert_eq!(check((3840, 2160), (16, 10), (8, 8), 2), 3);
+        assert_eq!(check((10, 10), (400, 400), (8, 8), 1), 2);
+        assert_eq!(check((1000, 10), (10, 1000), (16, 16), 1), 2);
+        assert!(plan_scale_passes((1 << 20, 2), (1, 2), (2, 2), 1).is_none());
+    }
+
+    #[test]
+    fn test_needs_multipass() {
+        let rga = Rga::new().unwrap();
+        let rga2 = crate::RgaCapabilities::parse("rga2, core 4: version: 3.2.63318");
+        rga.caps.set(rga2).ok().unwrap();
+        let rect = |w, h| RgaInfo {
+            rect: RgaRectBuilder::new()
+                .size(w, h)
+                .format(RgaPixelFormat::Rgba8888)
+                .build(),
+            ..Default::default()
+        };
+        let limit = rga.scale_limit(&rect(1, 1), &rect(1, 1));
+        let big = 64 * limit.0 as i32;
+        assert!(rga.needs_multipass(&rect(big, 64), &rect(32, 32)));
+        assert!(!rga.needs_multipass(&rect(1920, 1080), &rect(1280, 720)));
+    }
+}
@@ -0,0 +1,129 @@
+//! 神经网络输入归一化（量化）。
+//!
+use super::ffi;
+use super::geom::invalid_input;
+use std::io;
+
+/// 硬件缩放系数的小数位数。
+pub const NN_SCALE_FRAC_BITS: u32 = 8;
+/// 硬件缩放系数的最大原始值（10 位）。
+pub const NN_SCALE_MAX: i32 = 0x3ff;
+/// 硬件偏移量的最大绝对值。
+pub const NN_OFFSET_MAX: i32 = 255;
+
+/// 一个描述逐通道归一化参数的类型。
+///
+/// 按 R、G、B 通道计算 `out = in * scale + offset`。
+///
+/// 硬件以 `out = (in + offset') * scale'` 的形式实现，其中 `scale'` 为
+/// 8 位小数的 10 位定点数（范围 `[0, 4)`），`offset'` 为 `[-255, 255]` 的整数，
+/// 转换带来的误差可通过 [`quantization_error`] 获得。
+///
+/// [`quantization_error`]: Normalize::quantization_error()
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Normalize {
+    /// R、G、B 通道的缩放系数。
+    pub scale: [f32; 3],
+    /// R、G、B 通道的偏移量。
+    pub offset: [f32; 3],
+}
+
+impl Normalize {
+    /// 创建一个归一化参数。
+    pub const fn new(scale: [f32; 3], offset: [f32; 3]) -> Self {
+        Self { scale, offset }
+    }
+
+    /// 使用均值及标准差创建，即 `out = (in - mean) / std`。
+    pub fn from_mean_std(mean: [f32; 3], std: [f32; 3]) -> Self {
+        let mut n = Self::default();
+        for i in 0..3 {
+            n.scale[i] = 1.0 / std[i];
+            n.offset[i] = -mean[i] / std[i];
+        }
+        n
+    }
+
+    /// 返回各通道硬件定点形式的 `(scale', offset')`。
+    fn quantize(&self) -> Result<[(i32, i32); 3], io::Error> {
+        let mut q = [(0, 0); 3];
+        for (i, q) in q.iter_mut().enumerate() {
+            let scale = (self.scale[i] * (1 << NN_SCALE_FRAC_BITS) as f32).round();
+            if !(0.0..=NN_SCALE_MAX as f32).contains(&scale) {
+                return Err(invalid_input("Normalize: scale out of range [0, 4)"));
+            }
+            let offset = if scale > 0.0 {
+                (self.offset[i] / self.scale[i]).round()
+            } else {
+                0.0
+            };
+            if !(-NN_OFFSET_MAX as f32..=NN_OFFSET_MAX as f32).contains(&offset) {
+                return Err(invalid_input("Normalize: offset out of range"));
+            }
+            *q = (scale as i32, offset as i32);
+        }
+        Ok(q)
+    }
+
+    /// 转换为硬件参数。
+    pub fn to_raw(&self) -> Result<ffi::rga_nn_t, io::Error> {
+        let [r, g, b] = self.quantize()?;
+        Ok(ffi::rga_nn_t {
+            nn_flag: 1,
+            scale_r: r.0,
+            scale_g: g.0,
+            scale_b: b.0,
+            offset_r: r.1,
+            offset_g: g.1,
+            offset_b: b.1,
+        })
+    }
+
+    /// 返回各通道在输入范围 `[0, 255]` 内由定点转换带来的最大绝对误差。
+    pub fn quantization_error(&self) -> Result<[f32; 3], io::Error> {
+        let q = self.quantize()?;
+        let mut err = [0f32; 3];
+        for i in 0..3 {
+            let (scale, offset) = q[i];
+            let scale = scale as f32 / (1 << NN_SCALE_FRAC_BITS) as f32;
+            // 误差随输入线性变化，最大值出现在输入范围的端点。
+            err[i] = [0.0f32, 255.0]
+                .iter()
+                .map(|x| {
+                    let exact = x * self.scale[i] + self.offset[i];
+                    let hw = (x + offset as f32) * scale;
+                    (hw - exact).abs()
+                })
+                .fold(0.0, f32::max);
+        }
+        Ok(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_to_raw() {
+        let n = Normalize::new([1.0, 0.5, 2.0], [-10.0, 8.0, 0.0]);
+        let nn = n.to_raw().unwrap();
+        assert_eq!(nn.nn_flag, 1);
+        assert_eq!((nn.scale_r, nn.scale_g, nn.scale_b), (256, 128, 512));
+        assert_eq!((nn.offset_r, nn.offset_g, nn.offset_b), (-10, 16, 0));
+        assert_eq!(n.quantization_error().unwrap(), [0.0; 3]);
+    }
+
+    #[test]
+    fn test_normalize_error() {
+        let n = Normalize::from_mean_std([123.675, 116.28, 103.53], [58.395, 57.12, 57.375]);
+        // 1/std 只能以 1/256 的精度表示，误差较明显。
+        let err = n.quantization_error().unwrap();
+        for e in err {
+            assert!(e > 0.1 && e < 0.5, "error = {}", e);
+        }
+        assert!(Normalize::new([4.0; 3], [0.0; 3]).to_raw().is_err());
+        assert!(Normalize::new([-0.5; 3], [0.0; 3]).to_raw().is_err());
+        assert!(Normalize::new([0.5; 3], [200.0; 3]).to_raw().is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! 可组合的旋转及翻转变换。
+//!
+use super::{ffi, RgaTransform};
+
+/// 一个描述图像方向变换的类型，即二面体群 D4 中的 8 个元素。
+///
+/// 变换表示为先顺时针旋转 `quarter_turns` 个 90 度，再按需水平翻转。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RgaOrientation {
+    quarter_turns: u8,
+    flip: bool,
+}
+
+impl RgaOrientation {
+    /// 不变换。
+    pub const IDENTITY: Self = Self::new(0, false);
+    /// 顺时针旋转 90 度。
+    pub const ROT_90: Self = Self::new(1, false);
+    /// 旋转 180 度。
+    pub const ROT_180: Self = Self::new(2, false);
+    /// 顺时针旋转 270 度。
+    pub const ROT_270: Self = Self::new(3, false);
+    /// 水平翻转。
+    pub const FLIP_H: Self = Self::new(0, true);
+    /// 垂直翻转。
+    pub const FLIP_V: Self = Self::new(2, true);
+    /// 沿主对角线翻转。
+    pub const TRANSPOSE: Self = Self::new(1, true);
+    /// 沿副对角线翻转。
+    pub const TRANSVERSE: Self = Self::new(3, true);
+
+    /// 创建先顺时针旋转 `quarter_turns` 个 90 度、再按需水平翻转的变换。
+    pub const fn new(quarter_turns: u8, flip: bool) -> Self {
+        Self {
+            quarter_turns: quarter_turns & 3,
+            flip,
+        }
+    }
+
+    /// 返回顺时针旋转的 90 度次数。
+    pub fn quarter_turns(self) -> u8 {
+        self.quarter_turns
+    }
+
+    /// 当包含水平翻转时返回 true。
+    pub fn is_flipped(self) -> bool {
+        self.flip
+    }
+
+    /// 返回先执行 `self` 再执行 `next` 的变换。
+    pub fn then(self, next: Self) -> Self {
+        // F·R = R⁻¹·F，因此将 next 的旋转移过 self 的翻转时需取反。
+        let turns = if self.flip {
+            self.quarter_turns.wrapping_sub(next.quarter_turns)
+        } else {
+            self.quarter_turns.wrapping_add(next.quarter_turns)
+        };
+        Self::new(turns, self.flip ^ next.flip)
+    }
+
+    /// 返回逆变换。
+    pub fn inverse(self) -> Self {
+        if self.flip {
+            self
+        } else {
+            Self::new(4 - self.quarter_turns, false)
+        }
+    }
+
+    /// 从 EXIF 方向标签（1 ~ 8）转换，返回将存储图像转为显示方向的变换。
+    pub fn from_exif(tag: u16) -> Option<Self> {
+        match tag {
+            1 => Some(Self::IDENTITY),
+            2 => Some(Self::FLIP_H),
+            3 => Some(Self::ROT_180),
+            4 => Some(Self::FLIP_V),
+            5 => Some(Self::TRANSPOSE),
+            6 => Some(Self::ROT_90),
+            7 => Some(Self::TRANSVERSE),
+            8 => Some(Self::ROT_270),
+            _ => None,
+        }
+    }
+
+    /// 返回对应的 EXIF 方向标签。
+    pub fn to_exif(self) -> u16 {
+        (1..=8)
+            .find(|&tag| Self::from_exif(tag) == Some(self))
+            .unwrap_or(1)
+    }
+
+    /// 编码为 `rotation` 字段的取值，旋转后的额外翻转位于高 4 位。
+    pub fn to_raw(self) -> i32 {
+        let rot = match self.quarter_turns {
+            0 => 0,
+            1 => ffi::HAL_TRANSFORM_ROT_90,
+            2 => ffi::HAL_TRANSFORM_ROT_180,
+            _ => ffi::HAL_TRANSFORM_ROT_270,
+        } as i32;
+        match (self.quarter_turns, self.flip) {
+            (_, false) => rot,
+            (0, true) => ffi::HAL_TRANSFORM_FLIP_H as i32,
+            (2, true) => ffi::HAL_TRANSFORM_FLIP_V as i32,
+            (_, true) => rot | (ffi::HAL_TRANSFORM_FLIP_H as i32) << 4,
+        }
+    }
+
+    /// 从 `rotation` 字段的取值解码，无效时返回 None。
+    pub fn from_raw(raw: i32) -> Option<Self> {
+        let base = match (raw & 0xf) as u32 {
+            0 => Self::IDENTITY,
+            ffi::HAL_TRANSFORM_FLIP_H => Self::FLIP_H,
+            ffi::HAL_TRANSFORM_FLIP_V => Self::FLIP_V,
+            ffi::HAL_TRANSFORM_ROT_90 => Self::ROT_90,
+            ffi::HAL_TRANSFORM_ROT_180 | ffi::HAL_TRANSFORM_FLIP_H_V => Self::ROT_180,
+            ffi::HAL_TRANSFORM_ROT_270 => Self::ROT_270,
+            _ => return None,
+        };
+        let extra = match (raw >> 4) as u32 {
+            0 => Self::IDENTITY,
+            ffi::HAL_TRANSFORM_FLIP_H => Self::FLIP_H,
+            ffi::HAL_TRANSFORM_FLIP_V => Self::FLIP_V,
+            ffi::HAL_TRANSFORM_FLIP_H_V => Self::ROT_180,
+            _ => return None,
+        };
+        Some(base.then(extra))
+    }
+
+    /// 返回 `width` x `height` 的源图像变换后的大小。
+    pub fn map_size(self, width: i32, height: i32) -> (i32, i32) {
+        if self.quarter_turns & 1 == 1 {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// 返回 `width` x `height` 的源图像中 `(x, y)` 处像素变换后的坐标。
+    pub fn map_point(self, x: i32, y: i32, width: i32, height: i32) -> (i32, i32) {
+        let (mut x, mut y, mut w, mut h) = (x, y, width, height);
+        for _ in 0..self.quarter_turns {
+            (x, y) = (h - 1 - y, x);
+            (w, h) = (h, w);
+        }
+        if self.flip {
+            x = w - 1 - x;
+        }
+        (x, y)
+    }
+}
+
+impl From<RgaTransform> for RgaOrientation {
+    fn from(trans: RgaTransform) -> Self {
+        match trans {
+            RgaTransform::FlipH => Self::FLIP_H,
+            RgaTransform::FlipV => Self::FLIP_V,
+            RgaTransform::FlipHV | RgaTransform::Rot180 => Self::ROT_180,
+            RgaTransform::Rot90 => Self::ROT_90,
+            RgaTransform::Rot270 => Self::ROT_270,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> impl Iterator<Item = RgaOrientation> {
+        (0..8).map(|i| RgaOrientation::new(i & 3, i >= 4))
+    }
+
+    #[test]
+    fn test_orientation_group() {
+        let (w, h) = (5, 3);
+        for a in all() {
+            assert_eq!(a.then(a.inverse()), RgaOrientation::IDENTITY);
+            assert_eq!(a.inverse().then(a), RgaOrientation::IDENTITY);
+            for b in all() {
+                let ab = a.then(b);
+                let (aw, ah) = a.map_size(w, h);
+                assert_eq!(ab.map_size(w, h), b.map_size(aw, ah));
+                for (x, y) in [(0, 0), (4, 0), (1, 2), (3, 1)] {
+                    let (ax, ay) = a.map_point(x, y, w, h);
+                    assert_eq!(ab.map_point(x, y, w, h), b.map_point(ax, ay, aw, ah));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_orientation_encoding() {
+        for o in all() {
+            assert_eq!(RgaOrientation::from_raw(o.to_raw()), Some(o));
+            assert_eq!(RgaOrientation::from_exif(o.to_exif()), Some(o));
+        }
+        assert_eq!(RgaOrientation::ROT_90.to_raw(), 4);
+        assert_eq!(RgaOrientation::FLIP_V.to_raw(), 2);
+        assert_eq!(RgaOrientation::TRANSPOSE.to_raw(), 0x14);
+        assert_eq!(
+            RgaOrientation::ROT_90.then(RgaOrientation::FLIP_H).to_raw(),
+            0x14
+        );
+        assert_eq!(RgaOrientation::TRANSPOSE.map_point(1, 2, 5, 3), (2, 1));
+        assert_eq!(
+            RgaOrientation::from_exif(6).unwrap().map_size(640, 480),
+            (480, 640)
+        );
+    }
+}
@@ -0,0 +1,365 @@
+//! OSD 叠加及自动反色。
+//!
+//! RGA 将 OSD 位图按块划分，统计每块背景的亮度，并在背景过亮或过暗时自动反色，
+//! 以保证文字在任何背景上都清晰可见。
+//!
+use super::geom::{bounds_of, invalid_input, with_region};
+use super::{ffi, Point, Rect, Rga, RgaInfo};
+use std::io;
+
+/// 一个描述 OSD 参数的类型。
+pub type OsdConfig = ffi::rga_osd_info;
+
+/// 三输入混合时使用 DST_OVER 模式，即 OSD 位图叠加在背景之上。
+const OSD_BLEND_DST_OVER: u32 = 0x0501;
+
+/// 一个描述 OSD 工作模式的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OsdMode {
+    /// 仅统计各块的背景亮度。
+    Statistics = 1 << 0,
+    /// 根据统计结果自动反色。
+    AutoInvert = 1 << 1,
+}
+
+/// 一个描述 OSD 块排列方向的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OsdDirection {
+    /// 水平排列。
+    Horizontal = 0,
+    /// 垂直排列。
+    Vertical = 1,
+}
+
+/// 一个描述 OSD 块宽度的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OsdBlockWidth {
+    /// 所有块宽度相同。
+    Fixed(u16),
+    /// 各块宽度不同，由硬件 RAM 中第 `index` 组宽度表给出。
+    Variable(u8),
+}
+
+/// 一个描述默认背景的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OsdBackground {
+    /// 默认背景为亮色。
+    Bright = 0,
+    /// 默认背景为暗色。
+    Dark = 1,
+}
+
+/// 一个描述 OSD 颜色来源的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OsdColorMode {
+    /// 使用位图自身的像素颜色。
+    Pixel = 0,
+    /// 使用配置的颜色。
+    External = 1,
+}
+
+/// 一个描述反色计算方式的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OsdInvertMode {
+    /// 使用反色系数计算。
+    Factor = 0,
+    /// 交换两种颜色。
+    Swap = 1,
+}
+
+/// 一个描述反色标志来源的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OsdInvertFlags {
+    /// 使用硬件内部统计得到的标志。
+    Internal,
+    /// 使用外部给出的标志：RAM 索引、上一帧及当前帧的每块标志位。
+    External { index: u16, last: u64, current: u64 },
+}
+
+/// 反色作用于 Y/G 通道。
+pub const OSD_INVERT_YG: u8 = 1 << 0;
+/// 反色作用于 Cr/Cb 或 R/B 通道。
+pub const OSD_INVERT_CRB: u8 = 1 << 1;
+/// 反色作用于 Alpha 通道。
+pub const OSD_INVERT_ALPHA: u8 = 1 << 2;
+/// 反色作用于颜色通道。
+pub const OSD_INVERT_COLOR: u8 = OSD_INVERT_YG | OSD_INVERT_CRB;
+
+/// 一个描述 OSD 参数构建器的类型。
+pub struct OsdConfigBuilder {
+    mode: OsdMode,
+    direction: OsdDirection,
+    width: OsdBlockWidth,
+    block_count: u8,
+    background: OsdBackground,
+    color_mode: OsdColorMode,
+    invert_channels: u8,
+    invert_mode: OsdInvertMode,
+    invert_flags: OsdInvertFlags,
+    threshold: u8,
+    factor: ffi::rga_osd_invert_factor,
+    bpp2: Option<(u32, u32, bool, bool)>,
+}
+
+impl OsdConfigBuilder {
+    /// 创建一个新的 OSD 参数构建器。
+    pub fn new() -> Self {
+        Self {
+            mode: OsdMode::AutoInvert,
+            direction: OsdDirection::Horizontal,
+            width: OsdBlockWidth::Fixed(32),
+            block_count: 1,
+            background: OsdBackground::Bright,
+            color_mode: OsdColorMode::Pixel,
+            invert_channels: OSD_INVERT_COLOR,
+            invert_mode: OsdInvertMode::Factor,
+            invert_flags: OsdInvertFlags::Internal,
+            threshold: 0x80,
+            factor: ffi::rga_osd_invert_factor {
+                alpha_max: 0xff,
+                alpha_min: 0,
+                yg_max: 0xff,
+                yg_min: 0,
+                crb_max: 0xff,
+                crb_min: 0,
+            },
+            bpp2: None,
+        }
+    }
+
+    /// 设置工作模式。
+    pub fn mode(mut self, mode: OsdMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// 设置块排列方向。
+    pub fn direction(mut self, direction: OsdDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// 设置块宽度。
+    pub fn block_width(mut self, width: OsdBlockWidth) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// 设置块数量。
+    pub fn block_count(mut self, count: u8) -> Self {
+        self.block_count = count;
+        self
+    }
+
+    /// 设置默认背景。
+    pub fn background(mut self, background: OsdBackground) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// 设置颜色来源。
+    pub fn color_mode(mut self, mode: OsdColorMode) -> Self {
+        self.color_mode = mode;
+        self
+    }
+
+    /// 设置反色作用的通道，为 `OSD_INVERT_*` 的组合。
+    pub fn invert_channels(mut self, channels: u8) -> Self {
+        self.invert_channels = channels;
+        self
+    }
+
+    /// 设置反色计算方式。
+    pub fn invert_mode(mut self, mode: OsdInvertMode) -> Self {
+        self.invert_mode = mode;
+        self
+    }
+
+    /// 设置反色标志来源。
+    pub fn invert_flags(mut self, flags: OsdInvertFlags) -> Self {
+        self.invert_flags = flags;
+        self
+    }
+
+    /// 设置触发反色的背景亮度阈值。
+    pub fn invert_threshold(mut self, threshold: u8) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// 设置反色系数，各参数为 `(min, max)`。
+    pub fn invert_factor(mut self, alpha: (u8, u8), yg: (u8, u8), crb: (u8, u8)) -> Self {
+        self.factor = ffi::rga_osd_invert_factor {
+            alpha_max: alpha.1,
+            alpha_min: alpha.0,
+            yg_max: yg.1,
+            yg_min: yg.0,
+            crb_max: crb.1,
+            crb_min: crb.0,
+        };
+        self
+    }
+
+    /// 设置 2bpp 位图的双色调色板，颜色值为 `0xAABBGGRR`。
+    ///
+    /// `ac_swap` 为 true 时 Alpha 位在前，`little_endian` 为 true 时按小端解析位图。
+    pub fn bpp2_palette(
+        mut self,
+        color0: u32,
+        color1: u32,
+        ac_swap: bool,
+        little_endian: bool,
+    ) -> Self {
+        self.bpp2 = Some((color0, color1, ac_swap, little_endian));
+        self
+    }
+
+    /// 生成 OSD 参数。
+    pub fn build(self) -> OsdConfig {
+        let mut info = OsdConfig {
+            enable: 1,
+            ..Default::default()
+        };
+
+        let ctrl = &mut info.mode_ctrl;
+        ctrl.mode = self.mode as u8;
+        ctrl.direction_mode = self.direction as u8;
+        match self.width {
+            OsdBlockWidth::Fixed(width) => {
+                ctrl.width_mode = 0;
+                ctrl.block_fix_width = width;
+            }
+            OsdBlockWidth::Variable(index) => {
+                ctrl.width_mode = 1;
+                ctrl.unfix_index = index;
+            }
+        }
+        ctrl.block_num = self.block_count;
+        ctrl.default_color_sel = self.background as u8;
+        ctrl.color_mode = self.color_mode as u8;
+        ctrl.invert_enable = self.invert_channels;
+        ctrl.invert_mode = self.invert_mode as u8;
+        ctrl.invert_thresh = self.threshold;
+        match self.invert_flags {
+            OsdInvertFlags::Internal => ctrl.invert_flags_mode = 0,
+            OsdInvertFlags::External {
+                index,
+                last,
+                current,
+            } => {
+                ctrl.invert_flags_mode = 1;
+                ctrl.flags_index = index;
+                info.un1.last_flags = last;
+                info.un2.cur_flags = current;
+            }
+        }
+
+        info.cal_factor = self.factor;
+
+        if let Some((color0, color1, ac_swap, little_endian)) = self.bpp2 {
+            info.bpp2_info.ac_swap = ac_swap as u8;
+            info.bpp2_info.endian_swap = little_endian as u8;
+            info.bpp2_info.color0.un1.value = color0;
+            info.bpp2_info.color1.un1.value = color1;
+        }
+
+        info
+    }
+}
+
+impl Default for OsdConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rga {
+    /// 将 OSD 位图 `osd` 叠加到目标图像 `at` 处，并按 `config` 进行自动反色。
+    pub fn osd(
+        &self,
+        osd: &RgaInfo,
+        dst: &mut RgaInfo,
+        at: Point,
+        config: &OsdConfig,
+    ) -> Result<(), io::Error> {
+        let osd_bounds = bounds_of(osd)?;
+        let region = Rect::new(at.x, at.y, osd_bounds.width, osd_bounds.height);
+        if !bounds_of(dst)?.contains(&region) {
+            return Err(invalid_input("osd region is outside of the target image"));
+        }
+        let mut bg = with_region(dst, &region);
+        bg.blend = OSD_BLEND_DST_OVER;
+        bg.osd_info = *config;
+        let mut fg = *osd;
+        let src = bg;
+        self.blit(&src, &mut bg, Some(&mut fg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osd_defaults() {
+        let info = OsdConfigBuilder::new().build();
+        assert_eq!(info.enable, 1);
+        assert_eq!(info.mode_ctrl.mode, 0b10);
+        assert_eq!(info.mode_ctrl.width_mode, 0);
+        assert_eq!(info.mode_ctrl.block_fix_width, 32);
+        assert_eq!(info.mode_ctrl.invert_enable, 0b011);
+        assert_eq!(info.mode_ctrl.invert_flags_mode, 0);
+        assert_eq!(unsafe { info.un1.last_flags }, 0);
+    }
+
+    #[test]
+    fn test_osd_encoding() {
+        let info = OsdConfigBuilder::new()
+            .mode(OsdMode::Statistics)
+            .direction(OsdDirection::Vertical)
+            .block_width(OsdBlockWidth::Variable(3))
+            .block_count(20)
+            .background(OsdBackground::Dark)
+            .color_mode(OsdColorMode::External)
+            .invert_channels(OSD_INVERT_YG | OSD_INVERT_ALPHA)
+            .invert_mode(OsdInvertMode::Swap)
+            .invert_flags(OsdInvertFlags::External {
+                index: 7,
+                last: 0x0000_0001_8000_0000,
+                current: 0xffff_0000_0000_00ff,
+            })
+            .invert_threshold(0x40)
+            .invert_factor((1, 2), (3, 4), (5, 6))
+            .bpp2_palette(0xff00_00ff, 0xffff_ffff, true, false)
+            .build();
+        let ctrl = &info.mode_ctrl;
+        assert_eq!(ctrl.mode, 0b01);
+        assert_eq!(ctrl.direction_mode, 1);
+        assert_eq!((ctrl.width_mode, ctrl.unfix_index), (1, 3));
+        assert_eq!(ctrl.block_fix_width, 0);
+        assert_eq!(ctrl.block_num, 20);
+        assert_eq!(ctrl.default_color_sel, 1);
+        assert_eq!(ctrl.color_mode, 1);
+        assert_eq!(ctrl.invert_enable, 0b101);
+        assert_eq!(ctrl.invert_mode, 1);
+        assert_eq!(ctrl.invert_thresh, 0x40);
+        assert_eq!((ctrl.invert_flags_mode, ctrl.flags_index), (1, 7));
+        unsafe {
+            assert_eq!(info.un1.un1.last_flags1, 0x8000_0000);
+            assert_eq!(info.un1.un1.last_flags0, 0x0000_0001);
+            assert_eq!(info.un2.cur_flags, 0xffff_0000_0000_00ff);
+            assert_eq!(info.bpp2_info.color0.un1.un1.red, 0xff);
+            assert_eq!(info.bpp2_info.color0.un1.un1.blue, 0x00);
+            assert_eq!(info.bpp2_info.color1.un1.value, 0xffff_ffff);
+        }
+        let f = &info.cal_factor;
+        assert_eq!((f.alpha_min, f.alpha_max, f.yg_min, f.yg_max), (1, 2, 3, 4));
+        assert_eq!((f.crb_min, f.crb_max), (5, 6));
+        assert_eq!((info.bpp2_info.ac_swap, info.bpp2_info.endian_swap), (1, 0));
+    }
+}
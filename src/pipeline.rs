@@ -0,0 +1,608 @@
+//! 声明式图像处理管线。
+//!
+//! 管线由若干命名阶段组成，每个阶段读取此前声明的某个阶段（或输入帧 [`PIPELINE_INPUT`]）的图像，
+//! 多个阶段读取同一输入即构成分支。中间图像在构建时一次性分配，此后每帧复用；
+//! 裁剪阶段仅引用输入图像的一部分，不分配中间图像。
+//!
+//! 各阶段操作由 [`PipelineBackend`] 执行，[`Arc<Rga>`] 使用硬件，[`SoftBackend`] 使用 CPU 参考实现。
+//!
+//! # Examples
+//! ```no_run
+//! use rkrga::{PipelineBuilder, Rect, Rga, RgaPixelFormat, RgaRectBuilder, Stage};
+//! use std::sync::Arc;
+//!
+//! let rga = Arc::new(Rga::new().unwrap());
+//! let input = RgaRectBuilder::new().size(1920, 1080).format(RgaPixelFormat::YCbCr420sp).build();
+//! let mut pipe = PipelineBuilder::new(&input)
+//!     .stage("roi", "input", Stage::Crop(Rect::new(320, 180, 1280, 720)))
+//!     .stage("rgb", "roi", Stage::Csc(RgaPixelFormat::Rgba8888))
+//!     .stage("thumb", "rgb", Stage::Scale { width: 320, height: 180, filter: Default::default() })
+//!     .build(rga)
+//!     .unwrap();
+//! # let frame = Default::default();
+//! pipe.run(&frame).unwrap();
+//! let thumb = pipe.output("thumb").unwrap();
+//! ```
+use super::alpha::BLEND_SRC_OVER_STRAIGHT;
+use super::geom::{bounds_of, invalid_input, with_region};
+use super::{
+    soft, soft::SoftImage, Point, Rect, Rga, RgaBuffer, RgaInfo, RgaInfoBuilder, RgaOrientation,
+    RgaPixelFormat, RgaRect, RgaRectBuilder, ScaleFilter,
+};
+use std::io;
+use std::sync::Arc;
+
+/// 输入帧的阶段名。
+pub const PIPELINE_INPUT: &str = "input";
+
+/// 一个执行管线各阶段操作的后端。
+///
+/// 各操作方法可能直接读写 `src`、`dst` 描述的内存，因此均为 `unsafe`：
+/// 调用者需保证这些图像在调用期间有效，且 `dst` 不与其他正在使用的引用重叠。
+pub trait PipelineBackend {
+    /// 中间图像的缓冲区类型。
+    type Buffer;
+
+    /// 分配一幅 `rect` 所描述的中间图像，返回缓冲区及描述它的操作信息。
+    fn alloc(&self, rect: &RgaRect) -> Result<(Self::Buffer, RgaInfo), io::Error>;
+
+    /// 将 `src` 图像缩放并转换格式后写入 `dst` 图像，插值方式由 `src` 的 `scale_mode` 指定。
+    ///
+    /// # Safety
+    /// 见 [`PipelineBackend`]。
+    unsafe fn scale(&self, src: &RgaInfo, dst: &mut RgaInfo) -> Result<(), io::Error>;
+
+    /// 将 `src` 图像按 `orient` 变换后写入 `dst` 图像。
+    ///
+    /// # Safety
+    /// 见 [`PipelineBackend`]。
+    unsafe fn rotate(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        orient: RgaOrientation,
+    ) -> Result<(), io::Error>;
+
+    /// 以非预乘 SRC_OVER 模式将 `src` 图像叠加到大小一致的 `dst` 图像上。
+    ///
+    /// # Safety
+    /// 见 [`PipelineBackend`]。
+    unsafe fn overlay(&self, src: &RgaInfo, dst: &mut RgaInfo) -> Result<(), io::Error>;
+}
+
+impl PipelineBackend for Arc<Rga> {
+    type Buffer = RgaBuffer;
+
+    fn alloc(&self, rect: &RgaRect) -> Result<(RgaBuffer, RgaInfo), io::Error> {
+        let bo = RgaBuffer::with_rect_mapped(Arc::clone(self), rect)?;
+        let info = *RgaInfoBuilder::new().bo(&bo).rect(rect).build();
+        Ok((bo, info))
+    }
+
+    unsafe fn scale(&self, src: &RgaInfo, dst: &mut RgaInfo) -> Result<(), io::Error> {
+        Rga::scale(self, src, dst)
+    }
+
+    unsafe fn rotate(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        orient: RgaOrientation,
+    ) -> Result<(), io::Error> {
+        Rga::rotate(self, src, dst, orient)
+    }
+
+    unsafe fn overlay(&self, src: &RgaInfo, dst: &mut RgaInfo) -> Result<(), io::Error> {
+        let src = RgaInfo {
+            blend: BLEND_SRC_OVER_STRAIGHT,
+            ..*src
+        };
+        self.blit(&src, dst, None)
+    }
+}
+
+/// 一个使用 CPU 参考实现的后端，仅支持 [`soft`] 模块支持的格式。
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SoftBackend;
+
+impl SoftBackend {
+    /// 返回 `info` 图像的字节数组，格式不受支持或未映射到用户空间时返回错误。
+    ///
+    /// # Safety
+    /// 调用者需保证 `info.virAddr` 指向的内存覆盖整个图像且在返回值使用期间有效，
+    /// 并且期间没有其他引用指向这段内存。
+    unsafe fn bytes<'a>(info: &RgaInfo) -> Result<&'a mut [u8], io::Error> {
+        let fmt = RgaPixelFormat::from_raw(info.rect.format);
+        if !soft::is_supported(fmt) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("soft backend does not support format {:?}", fmt),
+            ));
+        }
+        soft::info_bytes_mut(info).ok_or_else(|| invalid_input("image is not mapped to user space"))
+    }
+
+    /// 返回 `src`、`dst` 图像的字节数组，两者内存重叠时返回错误。
+    ///
+    /// # Safety
+    /// 调用者需保证两幅图像的内存在返回值使用期间有效，且没有其他引用指向这段内存。
+    unsafe fn pair<'a>(
+        src: &'a RgaInfo,
+        dst: &'a mut RgaInfo,
+    ) -> Result<(&'a [u8], &'a mut [u8]), io::Error> {
+        let range = |info: &RgaInfo| {
+            let start = info.virAddr as usize;
+            start..start + soft::image_len(&info.rect)
+        };
+        let (a, b) = (range(src), range(dst));
+        if !src.virAddr.is_null() && !dst.virAddr.is_null() && a.start < b.end && b.start < a.end {
+            return Err(invalid_input("source and destination images overlap"));
+        }
+        Ok((&*Self::bytes(src)?, Self::bytes(dst)?))
+    }
+}
+
+impl PipelineBackend for SoftBackend {
+    type Buffer = Vec<u8>;
+
+    fn alloc(&self, rect: &RgaRect) -> Result<(Vec<u8>, RgaInfo), io::Error> {
+        let mut buf = vec![0; soft::image_len(rect)];
+        let info = *RgaInfoBuilder::new()
+            .vir_addr(buf.as_mut_ptr())
+            .rect(rect)
+            .build();
+        Ok((buf, info))
+    }
+
+    unsafe fn scale(&self, src: &RgaInfo, dst: &mut RgaInfo) -> Result<(), io::Error> {
+        let filter = ScaleFilter::from_raw(src.scale_mode).unwrap_or_default();
+        let rect = dst.rect;
+        let (s, d) = Self::pair(src, dst)?;
+        soft::scale(s, &src.rect, d, &rect, filter);
+        Ok(())
+    }
+
+    unsafe fn rotate(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        orient: RgaOrientation,
+    ) -> Result<(), io::Error> {
+        let rect = dst.rect;
+        let (s, d) = Self::pair(src, dst)?;
+        soft::rotate(s, &src.rect, d, &rect, orient);
+        Ok(())
+    }
+
+    unsafe fn overlay(&self, src: &RgaInfo, dst: &mut RgaInfo) -> Result<(), io::Error> {
+        let rect = dst.rect;
+        let (s, d) = Self::pair(src, dst)?;
+        soft::blend_over(s, &src.rect, d, &rect);
+        Ok(())
+    }
+}
+
+/// 一个描述管线阶段操作的枚举。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// 裁剪输入图像中的区域，不分配中间图像。YUV 格式下区域须按色度采样对齐。
+    Crop(Rect),
+    /// 缩放到指定大小。
+    Scale {
+        width: i32,
+        height: i32,
+        filter: ScaleFilter,
+    },
+    /// 转换为指定的像素格式。
+    Csc(RgaPixelFormat),
+    /// 旋转或翻转。
+    Rotate(RgaOrientation),
+    /// 将名为 `source` 的阶段的图像叠加到输入图像的 `at` 位置。
+    Overlay { source: String, at: Point },
+}
+
+/// 一个描述已声明的图像来源的枚举。
+#[derive(Clone)]
+enum Decl {
+    Source(Box<RgaInfo>),
+    Stage(String, Stage),
+}
+
+/// 一个已解析的管线阶段，输入帧及外部图像的 `stage` 为 None。
+#[derive(Clone, Debug)]
+struct Node {
+    name: String,
+    input: usize,
+    source: usize,
+    stage: Option<Stage>,
+    rect: RgaRect,
+}
+
+/// 一个用于声明 [`Pipeline`] 的构建器。
+#[derive(Clone)]
+pub struct PipelineBuilder {
+    input: RgaRect,
+    decls: Vec<(String, Decl)>,
+}
+
+impl PipelineBuilder {
+    /// 创建一个输入帧大小及格式与 `input` 一致的管线构建器。
+    pub fn new(input: &RgaRect) -> Self {
+        Self {
+            input: *input,
+            decls: Vec::new(),
+        }
+    }
+
+    /// 声明一幅名为 `name` 的外部图像，可作为叠加来源。
+    ///
+    /// # Safety
+    /// 调用者需保证 `info` 描述的内存在管线存续期间有效，且运行期间没有其他代码写入；
+    /// 使用 [`SoftBackend`] 时这段内存由 CPU 直接读取。
+    pub unsafe fn source(mut self, name: &str, info: RgaInfo) -> Self {
+        self.decls
+            .push((name.to_owned(), Decl::Source(Box::new(info))));
+        self
+    }
+
+    /// 声明一个名为 `name` 的阶段，对名为 `input` 的阶段的图像执行 `stage` 操作。
+    pub fn stage(mut self, name: &str, input: &str, stage: Stage) -> Self {
+        self.decls
+            .push((name.to_owned(), Decl::Stage(input.to_owned(), stage)));
+        self
+    }
+
+    /// 校验各阶段并使用 `backend` 分配中间图像，生成管线。
+    pub fn build<B: PipelineBackend>(self, backend: B) -> Result<Pipeline<B>, io::Error> {
+        let input = RgaRectBuilder::new()
+            .size(self.input.width, self.input.height)
+            .format(RgaPixelFormat::from_raw(self.input.format))
+            .build();
+        let mut nodes = vec![Node {
+            name: PIPELINE_INPUT.to_owned(),
+            input: 0,
+            source: 0,
+            stage: None,
+            rect: input,
+        }];
+        let mut buffers = Vec::new();
+        let mut infos = vec![RgaInfo::default()];
+        let find = |nodes: &[Node], name: &str| {
+            nodes
+                .iter()
+                .position(|n| n.name == name)
+                .ok_or_else(|| invalid_input(&format!("unknown pipeline stage `{}`", name)))
+        };
+        for (name, decl) in self.decls {
+            if nodes.iter().any(|n| n.name == name) {
+                return Err(invalid_input(&format!(
+                    "duplicate pipeline stage `{}`",
+                    name
+                )));
+            }
+            let (input, stage) = match decl {
+                Decl::Source(info) => {
+                    bounds_of(&info)?;
+                    nodes.push(Node {
+                        name,
+                        input: 0,
+                        source: 0,
+                        stage: None,
+                        rect: info.rect,
+                    });
+                    infos.push(*info);
+                    continue;
+                }
+                Decl::Stage(input, stage) => (find(&nodes, &input)?, stage),
+            };
+            let prev = nodes[input].rect;
+            let fmt = RgaPixelFormat::from_raw(prev.format);
+            let shape = |w, h, fmt| RgaRectBuilder::new().size(w, h).format(fmt).build();
+            let mut source = 0;
+            let rect = match &stage {
+                Stage::Crop(r) => {
+                    if r.is_empty() || !Rect::with_size(prev.width, prev.height).contains(r) {
+                        return Err(invalid_input(&format!(
+                            "crop of stage `{}` exceeds its input",
+                            name
+                        )));
+                    }
+                    let align = fmt.chroma_subsampling().unwrap_or((1, 1));
+                    if !r.is_aligned_to(align) {
+                        return Err(invalid_input(&format!(
+                            "crop of stage `{}` is not aligned to chroma subsampling",
+                            name
+                        )));
+                    }
+                    shape(r.width, r.height, fmt)
+                }
+                Stage::Scale { width, height, .. } => {
+                    if *width <= 0 || *height <= 0 {
+                        return Err(invalid_input(&format!(
+                            "scale of stage `{}` has an empty size",
+                            name
+                        )));
+                    }
+                    shape(*width, *height, fmt)
+                }
+                Stage::Csc(fmt) => shape(prev.width, prev.height, *fmt),
+                Stage::Rotate(orient) => {
+                    let (w, h) = orient.map_size(prev.width, prev.height);
+                    shape(w, h, fmt)
+                }
+                Stage::Overlay { source: s, at } => {
+                    source = find(&nodes, s)?;
+                    let r = &nodes[source].rect;
+                    let region = Rect::new(at.x, at.y, r.width, r.height);
+                    if !Rect::with_size(prev.width, prev.height).contains(&region) {
+                        return Err(invalid_input(&format!(
+                            "overlay of stage `{}` exceeds its input",
+                            name
+                        )));
+                    }
+                    shape(prev.width, prev.height, fmt)
+                }
+            };
+            // 裁剪阶段的图像在运行时由输入图像得出，其余阶段的图像为中间图像。
+            if let Stage::Crop(_) = stage {
+                infos.push(RgaInfo::default());
+            } else {
+                let (buf, info) = backend.alloc(&rect)?;
+                buffers.push(buf);
+                infos.push(info);
+            }
+            nodes.push(Node {
+                name,
+                input,
+                source,
+                stage: Some(stage),
+                rect,
+            });
+        }
+        Ok(Pipeline {
+            backend,
+            nodes,
+            buffers,
+            infos,
+        })
+    }
+}
+
+/// 一个已构建的图像处理管线。
+pub struct Pipeline<B: PipelineBackend> {
+    backend: B,
+    nodes: Vec<Node>,
+    buffers: Vec<B::Buffer>,
+    infos: Vec<RgaInfo>,
+}
+
+impl<B: PipelineBackend> Pipeline<B> {
+    /// 返回执行操作的后端。
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// 返回管线分配的中间图像数量。
+    pub fn intermediate_count(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// 依次执行各阶段处理输入帧 `frame`。
+    ///
+    /// `frame` 的大小及格式须与构建时指定的一致。
+    ///
+    /// # Safety
+    /// 调用者需保证 `frame` 描述的内存在调用期间有效且不被写入，见 [`PipelineBackend`]。
+    pub unsafe fn run_unchecked(&mut self, frame: &RgaInfo) -> Result<(), io::Error> {
+        let expected = &self.nodes[0].rect;
+        let bounds = bounds_of(frame)?;
+        if (bounds.width, bounds.height, frame.rect.format)
+            != (expected.width, expected.height, expected.format)
+        {
+            return Err(invalid_input(
+                "frame size or format differs from the pipeline input",
+            ));
+        }
+        self.infos[0] = *frame;
+        for i in 1..self.nodes.len() {
+            let node = &self.nodes[i];
+            let src = self.infos[node.input];
+            let mut dst = self.infos[i];
+            match &node.stage {
+                None => {}
+                Some(Stage::Crop(r)) => self.infos[i] = with_region(&src, r),
+                Some(Stage::Scale { filter, .. }) => {
+                    let src = RgaInfo {
+                        scale_mode: *filter as i32,
+                        ..src
+                    };
+                    self.backend.scale(&src, &mut dst)?;
+                }
+                Some(Stage::Rotate(orient)) => self.backend.rotate(&src, &mut dst, *orient)?,
+                Some(Stage::Overlay { at, .. }) => {
+                    self.backend.scale(&src, &mut dst)?;
+                    let overlay = self.infos[node.source];
+                    let region = Rect::new(at.x, at.y, overlay.rect.width, overlay.rect.height);
+                    self.backend
+                        .overlay(&overlay, &mut with_region(&dst, &region))?;
+                }
+                Some(Stage::Csc(_)) => self.backend.scale(&src, &mut dst)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// 返回最近一次运行后名为 `name` 的阶段的图像，不存在时返回 None。
+    ///
+    /// 引用输入帧的图像仅在输入帧有效期间有效。
+    pub fn output(&self, name: &str) -> Option<RgaInfo> {
+        let i = self.nodes.iter().position(|n| n.name == name)?;
+        Some(self.infos[i])
+    }
+}
+
+impl Pipeline<Arc<Rga>> {
+    /// 依次执行各阶段处理输入帧 `frame`，各阶段由硬件执行。
+    ///
+    /// `frame` 的大小及格式须与构建时指定的一致。
+    pub fn run(&mut self, frame: &RgaInfo) -> Result<(), io::Error> {
+        // SAFETY: 硬件后端与 `Rga::blit` 一样，由驱动访问图像内存。
+        unsafe { self.run_unchecked(frame) }
+    }
+}
+
+impl Pipeline<SoftBackend> {
+    /// 依次执行各阶段处理输入帧 `frame`。
+    ///
+    /// `frame` 的大小及格式须与构建时指定的一致。
+    pub fn run(&mut self, frame: &SoftImage) -> Result<(), io::Error> {
+        if frame.data.len() < soft::image_len(&frame.rect) {
+            return Err(invalid_input("frame data is smaller than its rect"));
+        }
+        let info = *RgaInfoBuilder::new()
+            .vir_addr(frame.data.as_ptr() as *mut u8)
+            .rect(&frame.rect)
+            .build();
+        // SAFETY: `frame` 在调用期间被借用且不会被写入，中间图像由 `alloc` 分配。
+        unsafe { self.run_unchecked(&info) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info_of(img: &mut SoftImage) -> RgaInfo {
+        *RgaInfoBuilder::new()
+            .vir_addr(img.data.as_mut_ptr())
+            .rect(&img.rect)
+            .build()
+    }
+
+    fn read(info: &RgaInfo, x: i32, y: i32) -> [u8; 4] {
+        // 输出图像由管线持有，读取期间没有其他引用。
+        let buf = unsafe { SoftBackend::bytes(info) }.unwrap();
+        soft::read(buf, &info.rect, x, y)
+    }
+
+    #[test]
+    fn test_soft_pipeline() {
+        let mut frame = SoftImage::new(16, 8, RgaPixelFormat::Rgba8888);
+        let mut logo = SoftImage::new(2, 2, RgaPixelFormat::Rgba8888);
+        logo.put(0, 0, [255, 0, 0, 255]);
+        logo.put(1, 1, [0, 0, 255, 128]);
+        // SAFETY: `logo` 在管线使用期间有效且不被修改。
+        let mut pipe =
+            unsafe { PipelineBuilder::new(&frame.rect).source("logo", info_of(&mut logo)) }
+                .stage("roi", PIPELINE_INPUT, Stage::Crop(Rect::new(8, 0, 8, 8)))
+                .stage(
+                    "half",
+                    "roi",
+                    Stage::Scale {
+                        width: 4,
+                        height: 4,
+                        filter: ScaleFilter::Nearest,
+                    },
+                )
+                .stage("rgb", "half", Stage::Csc(RgaPixelFormat::Rgb565))
+                .stage("rot", "rgb", Stage::Rotate(RgaOrientation::ROT_90))
+                .stage(
+                    "osd",
+                    "half",
+                    Stage::Overlay {
+                        source: "logo".into(),
+                        at: Point::new(2, 2),
+                    },
+                )
+                .build(SoftBackend)
+                .unwrap();
+        assert_eq!(pipe.intermediate_count(), 4);
+
+        for v in [10u8, 200] {
+            for y in 0..8 {
+                for x in 0..16 {
+                    frame.put(x, y, [x as u8 * 16, y as u8 * 32, v, 255]);
+                }
+            }
+            pipe.run(&frame).unwrap();
+            assert_eq!(pipe.intermediate_count(), 4);
+
+            // 最近邻缩小一半后 (1, 0) 取自裁剪区域的 (3, 1)，即输入帧的 (11, 1)。
+            let half = pipe.output("half").unwrap();
+            assert_eq!(read(&half, 1, 0), [176, 32, v, 255]);
+            let rot = pipe.output("rot").unwrap();
+            assert_eq!((rot.rect.width, rot.rect.height), (4, 4));
+            let mut px = [0u8; 2];
+            soft::encode(RgaPixelFormat::Rgb565, [176, 32, v, 255], &mut px);
+            // 旋转 90 度后 (1, 0) 位于 (3, 1)。
+            assert_eq!(read(&rot, 3, 1), soft::decode(RgaPixelFormat::Rgb565, &px));
+            let osd = pipe.output("osd").unwrap();
+            assert_eq!(read(&osd, 0, 0), read(&half, 0, 0));
+            assert_eq!(read(&osd, 2, 2), [255, 0, 0, 255]);
+            assert_eq!(
+                read(&osd, 3, 3),
+                soft::blend_over_pixel([0, 0, 255, 128], read(&half, 3, 3))
+            );
+        }
+    }
+
+    #[test]
+    fn test_pipeline_validation() {
+        let rect = RgaRectBuilder::new()
+            .size(16, 8)
+            .format(RgaPixelFormat::Rgba8888)
+            .build();
+        let build = |stage| {
+            PipelineBuilder::new(&rect)
+                .stage("a", PIPELINE_INPUT, stage)
+                .build(SoftBackend)
+                .map(|_| ())
+        };
+        assert!(build(Stage::Crop(Rect::new(8, 0, 8, 8))).is_ok());
+        assert!(build(Stage::Crop(Rect::new(9, 0, 8, 8))).is_err());
+        assert!(build(Stage::Overlay {
+            source: PIPELINE_INPUT.into(),
+            at: Point::new(1, 0),
+        })
+        .is_err());
+        let err = PipelineBuilder::new(&rect)
+            .stage("a", "missing", Stage::Csc(RgaPixelFormat::Rgb888))
+            .build(SoftBackend)
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(PipelineBuilder::new(&rect)
+            .stage("a", PIPELINE_INPUT, Stage::Csc(RgaPixelFormat::Rgb888))
+            .stage("a", PIPELINE_INPUT, Stage::Csc(RgaPixelFormat::Rgb888))
+            .build(SoftBackend)
+            .is_err());
+
+        let mut pipe = PipelineBuilder::new(&rect).build(SoftBackend).unwrap();
+        let mut small = SoftImage::new(8, 8, RgaPixelFormat::Rgba8888);
+        assert!(pipe.run(&small).is_err());
+        let mut short = SoftImage::new(16, 8, RgaPixelFormat::Rgba8888);
+        short.data.truncate(16);
+        assert!(pipe.run(&short).is_err());
+
+        // YUV 图像的裁剪区域须为偶数偏移及大小。
+        let nv12 = RgaRectBuilder::new()
+            .size(16, 8)
+            .format(RgaPixelFormat::YCbCr420sp)
+            .build();
+        let crop = |r| {
+            PipelineBuilder::new(&nv12)
+                .stage("a", PIPELINE_INPUT, Stage::Crop(r))
+                .build(SoftBackend)
+                .map(|_| ())
+        };
+        assert!(crop(Rect::new(2, 2, 8, 4)).is_ok());
+        assert!(crop(Rect::new(1, 2, 8, 4)).is_err());
+        assert!(crop(Rect::new(2, 2, 7, 4)).is_err());
+
+        // 软件后端拒绝源、目标内存重叠的操作。
+        let info = info_of(&mut small);
+        let mut same = info;
+        let err = unsafe { SoftBackend.scale(&info, &mut same) }.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -1,7 +1,7 @@
 use super::ffi;
 
 /// 一个描述 RGA 像素格式的枚举。
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum RgaPixelFormat {
     Rgba8888,      // RK_FORMAT_RGBA_8888 = 0,
     Rgbx8888,      // RK_FORMAT_RGBX_8888 = 256,
@@ -131,6 +131,127 @@ impl RgaPixelFormat {
             Unknown => 32,
         }
     }
+
+    /// 除 `Unknown` 外的所有格式。
+    const ALL: &'static [Self] = {
+        use RgaPixelFormat::*;
+        &[
+            Rgba8888,
+            Rgbx8888,
+            Rgb888,
+            Bgra8888,
+            Rgb565,
+            Rgba5551,
+            Rgba4444,
+            Bgr888,
+            YCbCr422sp,
+            YCbCr422p,
+            YCbCr420sp,
+            YCbCr420p,
+            YCrCb422sp,
+            YCrCb422p,
+            YCrCb420sp,
+            YCrCb420p,
+            Bpp1,
+            Bpp2,
+            Bpp4,
+            Bpp8,
+            Y4,
+            YCbCr400,
+            Bgrx8888,
+            Yvyu422,
+            Yvyu420,
+            Vyuy422,
+            Vyuy420,
+            Yuyv422,
+            Yuyv420,
+            Uyvy422,
+            Uyvy420,
+            YCbCr420sp10b,
+            YCrCb420sp10b,
+            YCbCr422sp10b,
+            YCrCb422sp10b,
+            #[cfg(feature = "v1_2_5")]
+            Bgr565,
+            #[cfg(feature = "v1_2_5")]
+            Bgra5551,
+            #[cfg(feature = "v1_2_5")]
+            Bgra4444,
+            #[cfg(feature = "v1_3_0")]
+            Argb8888,
+            #[cfg(feature = "v1_3_0")]
+            Xrgb8888,
+            #[cfg(feature = "v1_3_0")]
+            Argb5551,
+            #[cfg(feature = "v1_3_0")]
+            Argb4444,
+            #[cfg(feature = "v1_3_0")]
+            Abgr8888,
+            #[cfg(feature = "v1_3_0")]
+            Xbgr8888,
+            #[cfg(feature = "v1_3_0")]
+            Abgr5551,
+            #[cfg(feature = "v1_3_0")]
+            Abgr4444,
+            #[cfg(feature = "v1_7_2")]
+            Rgba2Bpp,
+        ]
+    };
+
+    /// 从图像区域信息中的原始格式值转换，无法识别的值返回 `Unknown`。
+    pub(crate) fn from_raw(format: i32) -> Self {
+        // 10 位 YUV 422 格式在这些版本中有两个取值。
+        #[cfg(feature = "v1_9_3")]
+        {
+            use ffi::RgaSURF_FORMAT::*;
+            if format == RK_FORMAT_YCbCr_422_10b_SP as i32 {
+                return Self::YCbCr422sp10b;
+            }
+            if format == RK_FORMAT_YCrCb_422_10b_SP as i32 {
+                return Self::YCrCb422sp10b;
+            }
+        }
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|&f| ffi::RgaSURF_FORMAT::from(f) as i32 == format)
+            .unwrap_or(Self::Unknown)
+    }
+
+    /// 当为 YUV 格式时返回 true。
+    pub fn is_yuv(&self) -> bool {
+        self.chroma_subsampling().is_some()
+    }
+
+    /// 当格式带有 Alpha 分量时返回 true。
+    pub fn has_alpha(&self) -> bool {
+        use RgaPixelFormat::*;
+        match self {
+            Rgba8888 | Bgra8888 | Rgba5551 | Rgba4444 => true,
+            #[cfg(feature = "v1_2_5")]
+            Bgra5551 | Bgra4444 => true,
+            #[cfg(feature = "v1_3_0")]
+            Argb8888 | Argb5551 | Argb4444 | Abgr8888 | Abgr5551 | Abgr4444 => true,
+            #[cfg(feature = "v1_7_2")]
+            Rgba2Bpp => true,
+            _ => false,
+        }
+    }
+
+    /// 返回 YUV 格式色度分量的水平及垂直采样间隔，非 YUV 格式返回 None。
+    pub fn chroma_subsampling(&self) -> Option<(i32, i32)> {
+        use RgaPixelFormat::*;
+        match self {
+            YCbCr420sp | YCbCr420p | YCrCb420sp | YCrCb420p => Some((2, 2)),
+            Yvyu420 | Vyuy420 | Yuyv420 | Uyvy420 => Some((2, 2)),
+            YCbCr420sp10b | YCrCb420sp10b => Some((2, 2)),
+            YCbCr422sp | YCbCr422p | YCrCb422sp | YCrCb422p => Some((2, 1)),
+            Yvyu422 | Vyuy422 | Yuyv422 | Uyvy422 => Some((2, 1)),
+            YCbCr422sp10b | YCrCb422sp10b => Some((2, 1)),
+            Y4 | YCbCr400 => Some((1, 1)),
+            _ => None,
+        }
+    }
 }
 
 impl From<ffi::RgaSURF_FORMAT> for RgaPixelFormat {
@@ -282,3 +403,23 @@ impl From<RgaPixelFormat> for ffi::RgaSURF_FORMAT {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_raw() {
+        for &fmt in RgaPixelFormat::ALL {
+            let raw = ffi::RgaSURF_FORMAT::from(fmt) as i32;
+            assert_eq!(RgaPixelFormat::from_raw(raw), fmt);
+        }
+        assert_eq!(
+            RgaPixelFormat::from_raw(ffi::RgaSURF_FORMAT::RK_FORMAT_UNKNOWN as i32),
+            RgaPixelFormat::Unknown
+        );
+        for raw in [-1, 1, 255, 0x7fff_ffff, i32::MIN] {
+            assert_eq!(RgaPixelFormat::from_raw(raw), RgaPixelFormat::Unknown);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! 光栅操作 (ROP3)。
+//!
+use super::RgaRop;
+use std::ops::Not;
+
+/// 一个描述 RGA 三元光栅操作码 (ROP3) 的类型。
+///
+/// 操作码的第 `(P << 2) | (S << 1) | D` 位即为图案 (P)、源 (S)、
+/// 目标 (D) 取对应值时的结果，与 GDI 的 ROP3 编码一致，共 256 种。
+///
+/// # Note
+/// 经 `c_RkRgaBlit` 提交时没有图案输入，硬件按图案全 0 计算，
+/// 因此只有低 4 位有效，这也是 librga 将 XOR 定义为 `0xf6` 的原因。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RgaRop3(u8);
+
+impl RgaRop3 {
+    /// DEST=0.
+    pub const BLACKNESS: Self = Self(0x00);
+    /// DEST=NOT(SRC OR DEST).
+    pub const NOTSRCERASE: Self = Self(0x11);
+    /// DEST=NOT(SRC).
+    pub const NOTSRCCOPY: Self = Self(0x33);
+    /// DEST=(SRC AND NOT(DEST)).
+    pub const SRCERASE: Self = Self(0x44);
+    /// DEST=NOT(DEST).
+    pub const DSTINVERT: Self = Self(0x55);
+    /// DEST=(PAT XOR DEST).
+    pub const PATINVERT: Self = Self(0x5a);
+    /// DEST=(SRC XOR DEST).
+    pub const SRCINVERT: Self = Self(0x66);
+    /// DEST=(SRC AND DEST).
+    pub const SRCAND: Self = Self(0x88);
+    /// DEST=(NOT(SRC) OR DEST).
+    pub const MERGEPAINT: Self = Self(0xbb);
+    /// DEST=(PAT AND SRC).
+    pub const MERGECOPY: Self = Self(0xc0);
+    /// DEST=SRC.
+    pub const SRCCOPY: Self = Self(0xcc);
+    /// DEST=(SRC OR DEST).
+    pub const SRCPAINT: Self = Self(0xee);
+    /// DEST=PAT.
+    pub const PATCOPY: Self = Self(0xf0);
+    /// DEST=(PAT OR NOT(SRC) OR DEST).
+    pub const PATPAINT: Self = Self(0xfb);
+    /// DEST=1.
+    pub const WHITENESS: Self = Self(0xff);
+
+    /// 使用原始操作码创建。
+    pub const fn new(code: u8) -> Self {
+        Self(code)
+    }
+
+    /// 使用布尔函数 `f(pat, src, dest)` 生成操作码。
+    ///
+    /// # Examples
+    /// ```
+    /// use rkrga::RgaRop3;
+    ///
+    /// let rop = RgaRop3::from_fn(|_, s, d| s ^ d);
+    /// assert_eq!(rop, RgaRop3::SRCINVERT);
+    /// ```
+    pub fn from_fn<F: Fn(bool, bool, bool) -> bool>(f: F) -> Self {
+        let mut code = 0u8;
+        for i in 0..8 {
+            if f(i & 4 != 0, i & 2 != 0, i & 1 != 0) {
+                code |= 1 << i;
+            }
+        }
+        Self(code)
+    }
+
+    /// 返回原始操作码。
+    pub const fn code(self) -> u8 {
+        self.0
+    }
+
+    /// 当结果依赖图案时返回 true。
+    pub const fn uses_pattern(self) -> bool {
+        (self.0 >> 4) != (self.0 & 0x0f)
+    }
+
+    /// 当结果依赖源图像时返回 true。
+    pub const fn uses_src(self) -> bool {
+        ((self.0 >> 2) & 0x33) != (self.0 & 0x33)
+    }
+
+    /// 当结果依赖目标图像时返回 true。
+    pub const fn uses_dst(self) -> bool {
+        ((self.0 >> 1) & 0x55) != (self.0 & 0x55)
+    }
+
+    /// 按位计算 `pat`、`src`、`dest` 的操作结果。
+    pub const fn eval(self, pat: u32, src: u32, dest: u32) -> u32 {
+        let mut out = 0u32;
+        let mut i = 0;
+        while i < 8 {
+            if self.0 & (1 << i) != 0 {
+                let p = if i & 4 != 0 { pat } else { !pat };
+                let s = if i & 2 != 0 { src } else { !src };
+                let d = if i & 1 != 0 { dest } else { !dest };
+                out |= p & s & d;
+            }
+            i += 1;
+        }
+        out
+    }
+
+    /// 在 CPU 上对字节数据执行操作，结果写回 `dest`。
+    ///
+    /// 光栅操作是按位进行的，因此与像素格式无关，`pat` 会重复应用于每个字节。
+    ///
+    /// # Panics
+    /// 当 `src` 与 `dest` 长度不同时触发。
+    pub fn apply(self, src: &[u8], dest: &mut [u8], pat: u8) {
+        assert_eq!(src.len(), dest.len(), "RgaRop3: length mismatch!");
+        for (d, s) in dest.iter_mut().zip(src) {
+            *d = self.eval(pat as u32, *s as u32, *d as u32) as u8;
+        }
+    }
+}
+
+impl Not for RgaRop3 {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        Self(!self.0)
+    }
+}
+
+impl From<u8> for RgaRop3 {
+    fn from(val: u8) -> Self {
+        Self(val)
+    }
+}
+
+impl From<RgaRop3> for u8 {
+    fn from(val: RgaRop3) -> Self {
+        val.0
+    }
+}
+
+impl From<RgaRop> for RgaRop3 {
+    fn from(val: RgaRop) -> Self {
+        Self(val as i32 as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named_codes() {
+        assert_eq!(RgaRop3::from_fn(|_, _, _| false), RgaRop3::BLACKNESS);
+        assert_eq!(RgaRop3::from_fn(|_, s, d| !(s | d)), RgaRop3::NOTSRCERASE);
+        assert_eq!(RgaRop3::from_fn(|_, s, _| !s), RgaRop3::NOTSRCCOPY);
+        assert_eq!(RgaRop3::from_fn(|_, s, d| s & !d), RgaRop3::SRCERASE);
+        assert_eq!(RgaRop3::from_fn(|_, _, d| !d), RgaRop3::DSTINVERT);
+        assert_eq!(RgaRop3::from_fn(|p, _, d| p ^ d), RgaRop3::PATINVERT);
+        assert_eq!(RgaRop3::from_fn(|_, s, d| s ^ d), RgaRop3::SRCINVERT);
+        assert_eq!(RgaRop3::from_fn(|_, s, d| s & d), RgaRop3::SRCAND);
+        assert_eq!(RgaRop3::from_fn(|_, s, d| !s | d), RgaRop3::MERGEPAINT);
+        assert_eq!(RgaRop3::from_fn(|p, s, _| p & s), RgaRop3::MERGECOPY);
+        assert_eq!(RgaRop3::from_fn(|_, s, _| s), RgaRop3::SRCCOPY);
+        assert_eq!(RgaRop3::from_fn(|_, s, d| s | d), RgaRop3::SRCPAINT);
+        assert_eq!(RgaRop3::from_fn(|p, _, _| p), RgaRop3::PATCOPY);
+        assert_eq!(RgaRop3::from_fn(|p, s, d| p | !s | d), RgaRop3::PATPAINT);
+        assert_eq!(RgaRop3::from_fn(|_, _, _| true), RgaRop3::WHITENESS);
+    }
+
+    #[test]
+    fn test_eval_all_codes() {
+        for code in 0..=255u8 {
+            let rop = RgaRop3::new(code);
+            for i in 0..8u32 {
+                let p = if i & 4 != 0 { !0 } else { 0 };
+                let s = if i & 2 != 0 { !0 } else { 0 };
+                let d = if i & 1 != 0 { !0 } else { 0 };
+                let expected = if code & (1 << i) != 0 { !0 } else { 0 };
+                assert_eq!(rop.eval(p, s, d), expected);
+            }
+            assert_eq!((!rop).eval(0x0f, 0x33, 0x55), !rop.eval(0x0f, 0x33, 0x55));
+        }
+    }
+
+    #[test]
+    fn test_operands() {
+        assert!(!RgaRop3::SRCCOPY.uses_pattern());
+        assert!(RgaRop3::SRCCOPY.uses_src());
+        assert!(!RgaRop3::SRCCOPY.uses_dst());
+        assert!(RgaRop3::PATINVERT.uses_pattern());
+        assert!(!RgaRop3::PATINVERT.uses_src());
+        assert!(RgaRop3::PATINVERT.uses_dst());
+        assert!(!RgaRop3::BLACKNESS.uses_dst());
+    }
+
+    #[test]
+    fn test_legacy_rop_without_pattern() {
+        let cases = [
+            (RgaRop::SrcAndDest, RgaRop3::SRCAND),
+            (RgaRop::SrcOrDest, RgaRop3::SRCPAINT),
+            (RgaRop::NotDest, RgaRop3::DSTINVERT),
+            (RgaRop::NotSrc, RgaRop3::NOTSRCCOPY),
+            (RgaRop::SrcXorDest, RgaRop3::SRCINVERT),
+            (RgaRop::NotSrcXorDest, !RgaRop3::SRCINVERT),
+        ];
+        for (legacy, rop) in cases {
+            let legacy = RgaRop3::from(legacy);
+            assert_eq!(legacy.eval(0, 0x33, 0x55), rop.eval(0, 0x33, 0x55));
+        }
+    }
+
+    #[test]
+    fn test_apply_cursor_xor() {
+        let cursor = [0xff, 0x00, 0xf0, 0x0f];
+        let mut frame = [0x12, 0x34, 0x56, 0x78];
+        RgaRop3::SRCINVERT.apply(&cursor, &mut frame, 0);
+        assert_eq!(frame, [0xed, 0x34, 0xa6, 0x77]);
+        RgaRop3::SRCINVERT.apply(&cursor, &mut frame, 0);
+        assert_eq!(frame, [0x12, 0x34, 0x56, 0x78]);
+    }
+}
@@ -0,0 +1,56 @@
+//! 缩放插值方式。
+//!
+use super::geom::invalid_input;
+use super::{CoreMask, Rga, RgaInfo};
+use std::io;
+
+/// 一个描述缩放插值方式的枚举，取值写入 `scale_mode`。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum ScaleFilter {
+    /// 双线性插值，为硬件默认方式。
+    #[default]
+    Bilinear = 0,
+    /// 最近邻插值。
+    Nearest = 1,
+    /// 双三次插值。
+    Bicubic = 2,
+}
+
+impl ScaleFilter {
+    /// 从 `scale_mode` 的取值转换，无效时返回 None。
+    pub fn from_raw(mode: i32) -> Option<Self> {
+        match mode {
+            0 => Some(Self::Bilinear),
+            1 => Some(Self::Nearest),
+            2 => Some(Self::Bicubic),
+            _ => None,
+        }
+    }
+}
+
+impl Rga {
+    /// 使用指定的插值方式对象图像进行缩放操作。
+    ///
+    /// 硬件不支持该插值方式时返回 [`io::ErrorKind::Unsupported`] 错误。
+    pub fn scale_with(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        filter: ScaleFilter,
+    ) -> Result<(), io::Error> {
+        let src = RgaInfo {
+            scale_mode: filter as i32,
+            ..*src
+        };
+        self.scale(&src, dst)
+    }
+
+    /// 检查 `scale_mode` 中的插值方式是否有效且被硬件支持。
+    pub(crate) fn check_scale_mode(&self, info: &RgaInfo) -> Result<(), io::Error> {
+        match ScaleFilter::from_raw(info.scale_mode) {
+            Some(filter) => self.check_filter(filter, CoreMask::of(info)),
+            None => Err(invalid_input("invalid scale mode")),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! 调度核心选择及任务优先级。
+//!
+use super::RgaInfo;
+use std::ops::{BitOr, BitOrAssign};
+#[cfg(feature = "v1_4_0")]
+use {super::Rga, std::io};
+
+/// 一个描述可调度 RGA 核心集合的类型，与 `RGA3_SCHEDULER_CORE0` 等取值一致。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CoreMask(u32);
+
+impl CoreMask {
+    /// 由驱动自动选择。
+    pub const AUTO: Self = Self(0);
+    /// RGA3 核心 0。
+    pub const RGA3_CORE0: Self = Self(1);
+    /// RGA3 核心 1。
+    pub const RGA3_CORE1: Self = Self(2);
+    /// 所有 RGA3 核心。
+    pub const RGA3: Self = Self(3);
+    /// RGA2 核心 0。
+    pub const RGA2_CORE0: Self = Self(4);
+
+    /// 从原始取值创建。
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// 返回原始取值。
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// 当未指定任何核心时返回 true。
+    pub const fn is_auto(self) -> bool {
+        self.0 == 0
+    }
+
+    /// 当包含 `other` 中的所有核心时返回 true。
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// 返回操作信息中指定的核心集合，不支持调度的版本总是返回 [`AUTO`]。
+    ///
+    /// [`AUTO`]: CoreMask::AUTO
+    pub(crate) fn of(info: &RgaInfo) -> Self {
+        #[cfg(feature = "v1_4_0")]
+        return Self(info.core as u32);
+        #[cfg(not(feature = "v1_4_0"))]
+        {
+            let _ = info;
+            Self::AUTO
+        }
+    }
+}
+
+impl BitOr for CoreMask {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for CoreMask {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// 一个描述任务优先级的类型，取值 0 ~ 6，数值越大优先级越高。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Priority(u8);
+
+impl Priority {
+    /// 最低优先级，同时为默认值。
+    pub const LOWEST: Self = Self(0);
+    /// 最高优先级。
+    pub const HIGHEST: Self = Self(Self::MAX);
+
+    const MAX: u8 = 6;
+
+    /// 创建一个优先级，超出范围时返回 None。
+    pub const fn new(value: u8) -> Option<Self> {
+        if value <= Self::MAX {
+            Some(Self(value))
+        } else {
+            None
+        }
+    }
+
+    /// 返回优先级数值。
+    pub const fn value(self) -> u8 {
+        self.0
+    }
+}
+
+#[cfg(feature = "v1_4_0")]
+impl Rga {
+    /// 检查操作信息中的核心及优先级是否被硬件支持。
+    pub(crate) fn check_schedule(&self, info: &RgaInfo) -> Result<(), io::Error> {
+        if !(0..=Priority::MAX as i32).contains(&info.priority) {
+            return Err(super::geom::invalid_input("priority out of range 0 ~ 6"));
+        }
+        let caps = self.capabilities();
+        let mask = CoreMask::from_bits(info.core as u32);
+        let available = caps.core_mask();
+        // 未检测到硬件能力时不校验核心，交由驱动判断。
+        if caps.is_unknown() || available.contains(mask) {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "core mask {:#x} is not available, detected cores: {:#x}",
+                    mask.bits(),
+                    available.bits()
+                ),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "v1_4_0")]
+    use crate::{RgaCapabilities, RgaCoreInfo, RgaCoreKind};
+
+    #[test]
+    fn test_core_mask_and_priority() {
+        let mask = CoreMask::RGA3_CORE0 | CoreMask::RGA3_CORE1;
+        assert_eq!(mask, CoreMask::RGA3);
+        assert!(mask.contains(CoreMask::RGA3_CORE1));
+        assert!(!mask.contains(CoreMask::RGA2_CORE0));
+        assert!(mask.contains(CoreMask::AUTO));
+        assert_eq!(Priority::new(6), Some(Priority::HIGHEST));
+        assert_eq!(Priority::new(7), None);
+        assert!(Priority::LOWEST < Priority::HIGHEST);
+    }
+
+    #[cfg(feature = "v1_4_0")]
+    #[test]
+    fn test_check_schedule() {
+        let mut info = RgaInfo {
+            core: CoreMask::RGA3_CORE1.bits() as _,
+            ..Default::default()
+        };
+
+        // 硬件能力未知时不校验核心。
+        let rga = Rga::new().unwrap();
+        rga.caps.set(RgaCapabilities::default()).ok().unwrap();
+        assert!(rga.check_schedule(&info).is_ok());
+        info.priority = 7;
+        assert!(rga.check_schedule(&info).is_err());
+        info.priority = 0;
+
+        let rga = Rga::new().unwrap();
+        let rga2 = RgaCapabilities {
+            cores: vec![RgaCoreInfo::new(RgaCoreKind::Rga2, 4)],
+        };
+        rga.caps.set(rga2).ok().unwrap();
+        let err = rga.check_schedule(&info).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+        info.core = CoreMask::RGA2_CORE0.bits() as _;
+        assert!(rga.check_schedule(&info).is_ok());
+    }
+}
@@ -0,0 +1,229 @@
+//! 可复用的临时缓冲区。
+//!
+//! 临时缓冲区由 librga 分配并映射到用户空间，以 DMA 文件描述符参与硬件操作。
+//! 归还的缓冲区按先后顺序缓存，超出数量或总字节数上限时先释放最早归还的缓冲区。
+use super::{ffi, soft, Rga, RgaBuffer, RgaInfo, RgaPixelFormat, RgaRect};
+use std::collections::VecDeque;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::sync::Mutex;
+
+/// 缓存的缓冲区数量上限。
+const SCRATCH_CACHE_LEN: usize = 4;
+/// 缓存的缓冲区总字节数上限。
+const SCRATCH_CACHE_BYTES: usize = 64 << 20;
+
+/// 一个由 librga 分配的临时缓冲区及其 DMA 文件描述符。
+struct DmaScratch {
+    bo: RgaBuffer,
+    fd: Option<OwnedFd>,
+}
+
+// 缓冲区仅在持有池的锁或从池中取出后访问，映射地址不会被共享。
+unsafe impl Send for DmaScratch {}
+
+impl DmaScratch {
+    /// 分配一个可容纳 `rect` 图像的缓冲区。
+    fn alloc(rect: &RgaRect) -> Result<Self, io::Error> {
+        let len = soft::image_len(rect).max(1);
+        let bpp = RgaPixelFormat::from_raw(rect.format).bits_per_pixel();
+        let pitch = (rect.wstride.max(1) as usize * bpp).div_ceil(8).max(1);
+        let bo = RgaBuffer::detached_mapped(pitch as i32, len.div_ceil(pitch) as i32, 8)?;
+        let fd = bo
+            .dma_fd()
+            .ok()
+            .filter(|fd| *fd >= 0)
+            .map(|fd| unsafe { OwnedFd::from_raw_fd(fd) });
+        Ok(Self { bo, fd })
+    }
+}
+
+/// 一个缓存临时缓冲区的池，须在 [`Rga`] 反初始化之前清空。
+#[derive(Default)]
+pub(crate) struct ScratchPool {
+    bufs: Mutex<VecDeque<DmaScratch>>,
+}
+
+impl std::fmt::Debug for ScratchPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScratchPool")
+            .field("cached", &self.bufs.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl ScratchPool {
+    /// 取出一个可容纳 `rect` 图像的缓冲区，优先复用大小最接近的缓存。
+    pub(crate) fn take(&self, rect: &RgaRect) -> Result<ScratchBuffer<'_>, io::Error> {
+        let len = soft::image_len(rect);
+        let cached = {
+            let mut bufs = self.bufs.lock().unwrap();
+            bufs.iter()
+                .enumerate()
+                .filter(|(_, b)| b.bo.len() >= len)
+                .min_by_key(|(_, b)| b.bo.len())
+                .map(|(i, _)| i)
+                .and_then(|i| bufs.remove(i))
+        };
+        let buf = match cached {
+            Some(buf) => buf,
+            None => DmaScratch::alloc(rect)?,
+        };
+        Ok(ScratchBuffer {
+            pool: self,
+            buf: Some(buf),
+            len,
+        })
+    }
+
+    /// 归还缓冲区，超出缓存上限时释放最早归还的缓冲区。
+    fn put(&self, buf: DmaScratch) {
+        let mut bufs = self.bufs.lock().unwrap();
+        bufs.push_back(buf);
+        while bufs.len() > SCRATCH_CACHE_LEN
+            || bufs.iter().map(|b| b.bo.len()).sum::<usize>() > SCRATCH_CACHE_BYTES
+        {
+            bufs.pop_front();
+        }
+    }
+
+    /// 返回缓存的缓冲区数量。
+    #[cfg(test)]
+    pub(crate) fn cached(&self) -> usize {
+        self.bufs.lock().unwrap().len()
+    }
+
+    /// 释放所有缓存的缓冲区。
+    pub(crate) fn clear(&self) {
+        self.bufs.lock().unwrap().clear();
+    }
+}
+
+/// 一个从 [`ScratchPool`] 取出的缓冲区，离开作用域时归还。
+pub(crate) struct ScratchBuffer<'a> {
+    pool: &'a ScratchPool,
+    buf: Option<DmaScratch>,
+    len: usize,
+}
+
+impl ScratchBuffer<'_> {
+    fn scratch(&self) -> &DmaScratch {
+        self.buf.as_ref().unwrap()
+    }
+
+    /// 返回缓冲区的 DMA 文件描述符，无法获取时返回 -1。
+    pub(crate) fn fd(&self) -> i32 {
+        self.scratch().fd.as_ref().map_or(-1, |fd| fd.as_raw_fd())
+    }
+
+    /// 返回描述此缓冲区的操作信息，调度参数与 `like` 一致。
+    pub(crate) fn info(&mut self, rect: RgaRect, like: &RgaInfo) -> RgaInfo {
+        let info = RgaInfo {
+            fd: self.fd(),
+            virAddr: self.as_mut_ptr() as *mut _,
+            mmuFlag: 1,
+            rect,
+            format: rect.format,
+            sync_mode: ffi::RGA_BLIT_SYNC as i32,
+            ..Default::default()
+        };
+        #[cfg(feature = "v1_4_0")]
+        let info = RgaInfo {
+            in_fence_fd: -1,
+            out_fence_fd: -1,
+            core: like.core,
+            priority: like.priority,
+            ..info
+        };
+        #[cfg(not(feature = "v1_4_0"))]
+        let _ = like;
+        info
+    }
+}
+
+impl Deref for ScratchBuffer<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.scratch().bo.as_bytes()[..self.len]
+    }
+}
+
+impl DerefMut for ScratchBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        let len = self.len;
+        &mut self.buf.as_mut().unwrap().bo.as_mut_slice()[..len]
+    }
+}
+
+impl Drop for ScratchBuffer<'_> {
+    fn drop(&mut self) {
+        if let Some(buf) = self.buf.take() {
+            self.pool.put(buf);
+        }
+    }
+}
+
+impl Rga {
+    /// 释放多趟缩放、分块处理及自动对齐缓存的临时缓冲区。
+    pub fn release_scratch(&self) {
+        self.scratch.clear();
+        self.bounce.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RgaRectBuilder;
+
+    fn rect(width: i32, height: i32) -> RgaRect {
+        RgaRectBuilder::new()
+            .size(width, height)
+            .format(RgaPixelFormat::Rgba8888)
+            .build()
+    }
+
+    #[test]
+    fn test_scratch_pool_reuse() {
+        let _rga = Rga::new().unwrap();
+        let pool = ScratchPool::default();
+        {
+            let mut a = pool.take(&rect(5, 5)).unwrap();
+            let b = pool.take(&rect(10, 10)).unwrap();
+            assert_eq!((a.len(), b.len()), (100, 400));
+            assert_eq!(pool.cached(), 0);
+            let info = a.info(rect(5, 5), &RgaInfo::default());
+            assert_eq!(info.fd, a.fd());
+            assert_eq!(info.virAddr as *const u8, a.as_ptr());
+        }
+        assert_eq!(pool.cached(), 2);
+        let c = pool.take(&rect(10, 8)).unwrap();
+        assert_eq!(c.len(), 320);
+        assert!(c.scratch().bo.len() >= 400);
+        assert_eq!(pool.cached(), 1);
+        drop(c);
+        pool.clear();
+        assert_eq!(pool.cached(), 0);
+    }
+
+    #[test]
+    fn test_scratch_pool_trim() {
+        let _rga = Rga::new().unwrap();
+        let pool = ScratchPool::default();
+        let bufs: Vec<_> = (1..=SCRATCH_CACHE_LEN as i32 + 2)
+            .map(|i| pool.take(&rect(i * 4, 4)).unwrap())
+            .collect();
+        drop(bufs);
+        // 超出数量上限时释放最早归还的缓冲区。
+        assert_eq!(pool.cached(), SCRATCH_CACHE_LEN);
+        assert!(pool.take(&rect(4, 4)).unwrap().scratch().bo.len() >= 3 * 4 * 4 * 4);
+
+        // 超出总字节数上限的缓冲区不会留在缓存中。
+        pool.clear();
+        let side = 4096;
+        drop(pool.take(&rect(side, side + 1)).unwrap());
+        assert_eq!(pool.cached(), 0);
+    }
+}
@@ -0,0 +1,176 @@
+//! 基于预中断的分片输出。
+//!
+//! 预中断（`pre_intr`）使硬件在每写完 N 行后发出通知，供硬件直连的下游模块（如编码器）
+//! 提前开始处理。librga 目前不会将这些通知传递到用户空间，因此 [`SliceStream`]
+//! 在整个操作的栅栏触发后才依次产出各分片事件；分片的划分与硬件配置保持一致。
+use super::geom::invalid_input;
+use super::{ffi, Rga, RgaFence, RgaInfo};
+use std::io;
+use std::ops::Range;
+use std::time::Duration;
+
+/// 一个描述分片通知参数的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SliceConfig {
+    /// 写完前 `write_start` 行后首次通知。
+    pub write_start: u32,
+    /// 之后每写完 `write_step` 行通知一次。
+    pub write_step: u32,
+    /// 读取到第 `read_threshold` 行时通知，为 None 时不启用读中断。
+    pub read_threshold: Option<u32>,
+}
+
+impl SliceConfig {
+    /// 创建每 `rows` 行通知一次的分片参数。
+    pub fn every(rows: u32) -> Self {
+        Self {
+            write_start: rows,
+            write_step: rows,
+            read_threshold: None,
+        }
+    }
+
+    /// 设置首次通知前的行数。
+    pub fn write_start(mut self, rows: u32) -> Self {
+        self.write_start = rows;
+        self
+    }
+
+    /// 设置读中断的行数阈值。
+    ///
+    /// 用户空间无法释放暂停的读取，因此不会启用读暂停（`read_hold_en`）。
+    pub fn read_threshold(mut self, rows: u32) -> Self {
+        self.read_threshold = Some(rows);
+        self
+    }
+
+    /// 转换为硬件参数。
+    pub fn to_raw(&self) -> Result<ffi::rga_pre_intr_info, io::Error> {
+        if self.write_start == 0 || self.write_step == 0 {
+            return Err(invalid_input("slice rows must be positive"));
+        }
+        Ok(ffi::rga_pre_intr_info {
+            enable: 1,
+            read_intr_en: self.read_threshold.is_some() as u8,
+            write_intr_en: 1,
+            read_hold_en: 0,
+            read_threshold: self.read_threshold.unwrap_or(0),
+            write_start: self.write_start,
+            write_step: self.write_step,
+        })
+    }
+
+    /// 返回输出高度为 `height` 时依次就绪的行范围。
+    pub fn slices(&self, height: u32) -> Vec<Range<u32>> {
+        let mut slices = Vec::new();
+        let mut start = 0;
+        let mut end = self.write_start.max(1);
+        while start < height {
+            let stop = end.min(height);
+            slices.push(start..stop);
+            start = stop;
+            end = stop + self.write_step.max(1);
+        }
+        slices
+    }
+}
+
+/// 一个描述分片就绪事件序列的类型，由 [`Rga::blit_sliced`] 返回。
+///
+/// # 限制
+///
+/// 分片事件**不是**逐片完成的通知：第一个事件要等整个操作的栅栏触发后才会产出，
+/// 其余事件随即依次产出。在此之前读取任何输出行都不安全，因此不能据此提前启动下游处理。
+#[derive(Debug)]
+pub struct SliceStream {
+    fence: Option<RgaFence>,
+    slices: std::vec::IntoIter<Range<u32>>,
+}
+
+impl SliceStream {
+    /// 等待下一个分片就绪，超时返回 `TimedOut` 错误，全部完成后返回 None。
+    pub fn next_timeout(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Option<Result<Range<u32>, io::Error>> {
+        if let Some(fence) = &self.fence {
+            match fence.wait(timeout) {
+                Ok(true) => self.fence = None,
+                Ok(false) => return Some(Err(io::Error::from(io::ErrorKind::TimedOut))),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        self.slices.next().map(Ok)
+    }
+
+    /// 取走整个操作完成时触发的栅栏。
+    pub fn into_fence(self) -> RgaFence {
+        self.fence.unwrap_or_else(RgaFence::signaled)
+    }
+}
+
+impl Iterator for SliceStream {
+    type Item = Result<Range<u32>, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_timeout(None)
+    }
+}
+
+impl Rga {
+    /// 以异步模式对象图像进行比特操作，并按 `config` 启用分片通知。
+    ///
+    /// # 限制
+    ///
+    /// 整个操作只提交一次、只有一个栅栏，返回的 [`SliceStream`] 在全部输出写完后才产出分片事件，
+    /// 与 [`Rga::blit_fenced`] 等待栅栏后再按行处理相比并无延迟上的优势。
+    /// 硬件的预中断配置仅对直连硬件的下游模块生效。
+    pub fn blit_sliced(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        config: &SliceConfig,
+    ) -> Result<SliceStream, io::Error> {
+        let pre_intr = config.to_raw()?;
+        let src = RgaInfo { pre_intr, ..*src };
+        let mut sliced = RgaInfo { pre_intr, ..*dst };
+        let fence = self.blit_fenced(&src, &mut sliced, None, None)?;
+        Ok(SliceStream {
+            fence: Some(fence),
+            slices: config.slices(dst.rect.height as u32).into_iter(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_config_to_raw() {
+        let raw = SliceConfig::every(64).write_start(128).to_raw().unwrap();
+        assert_eq!((raw.enable, raw.write_intr_en, raw.read_intr_en), (1, 1, 0));
+        assert_eq!((raw.write_start, raw.write_step), (128, 64));
+        assert_eq!(raw.read_hold_en, 0);
+        let raw = SliceConfig::every(16).read_threshold(32).to_raw().unwrap();
+        assert_eq!(
+            (raw.read_intr_en, raw.read_hold_en, raw.read_threshold),
+            (1, 0, 32)
+        );
+        assert!(SliceConfig::every(0).to_raw().is_err());
+    }
+
+    #[test]
+    fn test_slices() {
+        let cfg = SliceConfig::every(256).write_start(100);
+        assert_eq!(cfg.slices(720), vec![0..100, 100..356, 356..612, 612..720]);
+        assert_eq!(SliceConfig::every(1080).slices(720), vec![0..720]);
+        let mut stream = SliceStream {
+            fence: Some(RgaFence::signaled()),
+            slices: SliceConfig::every(360).slices(720).into_iter(),
+        };
+        assert_eq!(stream.next().unwrap().unwrap(), 0..360);
+        assert_eq!(stream.next().unwrap().unwrap(), 360..720);
+        assert!(stream.next().is_none());
+    }
+}
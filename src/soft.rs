@@ -0,0 +1,714 @@
+//! CPU 参考实现。
+//!
+//! 用于验证硬件结果以及在硬件不支持时回退。
+//!
+//! 8 位分量的格式按内存中的字节顺序命名，例如 `Rgba8888` 依次为 R、G、B、A；
+//! 16 位格式按分量从高位到低位的顺序命名，以小端存储，例如 `Rgb565` 的 R 位于高 5 位。
+//! 颜色值 `u32` 与 [`Rga::fill`] 一致，为 `Rgba8888` 在内存中的小端字，即 `0xAABBGGRR`。
+//!
+//! [`Rga::fill`]: crate::Rga::fill()
+use super::geom::invalid_input;
+use super::{Rect, RgaInfo, RgaOrientation, RgaPixelFormat, RgaRect, RgaRectBuilder, ScaleFilter};
+use std::io;
+
+/// 一个描述 16 位格式各分量位宽及顺序（从高位到低位）的类型。
+type Packed16 = [(u8, u32); 4];
+
+const R: u8 = 0;
+const G: u8 = 1;
+const B: u8 = 2;
+const A: u8 = 3;
+const X: u8 = 4;
+
+/// 8 位分量格式的分量顺序。
+fn layout8(fmt: RgaPixelFormat) -> Option<&'static [u8]> {
+    use RgaPixelFormat::*;
+    Some(match fmt {
+        Rgba8888 => &[R, G, B, A],
+        Rgbx8888 => &[R, G, B, X],
+        Bgra8888 => &[B, G, R, A],
+        Bgrx8888 => &[B, G, R, X],
+        Rgb888 => &[R, G, B],
+        Bgr888 => &[B, G, R],
+        #[cfg(feature = "v1_3_0")]
+        Argb8888 => &[A, R, G, B],
+        #[cfg(feature = "v1_3_0")]
+        Xrgb8888 => &[X, R, G, B],
+        #[cfg(feature = "v1_3_0")]
+        Abgr8888 => &[A, B, G, R],
+        #[cfg(feature = "v1_3_0")]
+        Xbgr8888 => &[X, B, G, R],
+        _ => return None,
+    })
+}
+
+/// 16 位格式的分量位宽及顺序。
+fn layout16(fmt: RgaPixelFormat) -> Option<Packed16> {
+    use RgaPixelFormat::*;
+    Some(match fmt {
+        Rgb565 => [(R, 5), (G, 6), (B, 5), (X, 0)],
+        Rgba5551 => [(R, 5), (G, 5), (B, 5), (A, 1)],
+        Rgba4444 => [(R, 4), (G, 4), (B, 4), (A, 4)],
+        #[cfg(feature = "v1_2_5")]
+        Bgr565 => [(B, 5), (G, 6), (R, 5), (X, 0)],
+        #[cfg(feature = "v1_2_5")]
+        Bgra5551 => [(B, 5), (G, 5), (R, 5), (A, 1)],
+        #[cfg(feature = "v1_2_5")]
+        Bgra4444 => [(B, 4), (G, 4), (R, 4), (A, 4)],
+        #[cfg(feature = "v1_3_0")]
+        Argb5551 => [(A, 1), (R, 5), (G, 5), (B, 5)],
+        #[cfg(feature = "v1_3_0")]
+        Argb4444 => [(A, 4), (R, 4), (G, 4), (B, 4)],
+        #[cfg(feature = "v1_3_0")]
+        Abgr5551 => [(A, 1), (B, 5), (G, 5), (R, 5)],
+        #[cfg(feature = "v1_3_0")]
+        Abgr4444 => [(A, 4), (B, 4), (G, 4), (R, 4)],
+        _ => return None,
+    })
+}
+
+/// 当格式为 CPU 参考实现支持的 RGB 格式时返回 true。
+pub fn is_supported(fmt: RgaPixelFormat) -> bool {
+    layout8(fmt).is_some() || layout16(fmt).is_some()
+}
+
+/// 返回 RGB 格式每个 R、G、B、A 分量的位宽，不含的分量为 0。
+pub fn component_bits(fmt: RgaPixelFormat) -> Option<[u32; 4]> {
+    if let Some(l) = layout8(fmt) {
+        let mut bits = [0; 4];
+        for c in l.iter().filter(|c| **c != X) {
+            bits[*c as usize] = 8;
+        }
+        Some(bits)
+    } else {
+        layout16(fmt).map(|l| {
+            let mut bits = [0; 4];
+            for (c, n) in l.iter().filter(|(c, _)| *c != X) {
+                bits[*c as usize] = *n;
+            }
+            bits
+        })
+    }
+}
+
+/// 将 `n` 位分量按比例扩展为 8 位。
+pub(crate) fn expand(v: u32, n: u32) -> u8 {
+    match n {
+        0 => 0xff,
+        n if n >= 8 => v as u8,
+        n => {
+            let max = (1 << n) - 1;
+            ((v * 0xff + max / 2) / max) as u8
+        }
+    }
+}
+
+/// 将一个像素解码为 RGBA，缺失的 Alpha 为 255。
+///
+/// # Panics
+/// 当格式不受支持时触发。
+pub fn decode(fmt: RgaPixelFormat, px: &[u8]) -> [u8; 4] {
+    let mut out = [0, 0, 0, 0xff];
+    if let Some(l) = layout8(fmt) {
+        for (i, c) in l.iter().enumerate() {
+            if *c != X {
+                out[*c as usize] = px[i];
+            }
+        }
+    } else if let Some(l) = layout16(fmt) {
+        let mut v = u16::from_le_bytes([px[0], px[1]]) as u32;
+        for (c, n) in l.iter().rev() {
+            if *c != X {
+                out[*c as usize] = expand(v & ((1 << n) - 1), *n);
+            }
+            v >>= n;
+        }
+    } else {
+        panic!("soft: unsupported format {:?}", fmt);
+    }
+    out
+}
+
+/// 将 RGBA 编码为一个像素，多余的低位被截断。
+///
+/// # Panics
+/// 当格式不受支持时触发。
+pub fn encode(fmt: RgaPixelFormat, rgba: [u8; 4], px: &mut [u8]) {
+    if let Some(l) = layout8(fmt) {
+        for (i, c) in l.iter().enumerate() {
+            px[i] = if *c == X { 0xff } else { rgba[*c as usize] };
+        }
+    } else if let Some(l) = layout16(fmt) {
+        let mut v = 0u32;
+        for (c, n) in l.iter() {
+            let bits = if *c == X {
+                0
+            } else {
+                rgba[*c as usize] as u32 >> (8 - n)
+            };
+            v = (v << n) | bits;
+        }
+        px[..2].copy_from_slice(&(v as u16).to_le_bytes());
+    } else {
+        panic!("soft: unsupported format {:?}", fmt);
+    }
+}
+
+/// 返回图像区域中 `(x, y)` 处像素的字节偏移。
+fn pixel_offset(rect: &RgaRect, x: i32, y: i32) -> usize {
+    let bpp = RgaPixelFormat::from_raw(rect.format).bits_per_pixel();
+    let x = (rect.xoffset + x) as usize;
+    let y = (rect.yoffset + y) as usize;
+    (y * rect.wstride as usize + x) * bpp / 8
+}
+
+/// 读取图像区域中 `(x, y)` 处的像素。
+pub fn read(buf: &[u8], rect: &RgaRect, x: i32, y: i32) -> [u8; 4] {
+    let fmt = RgaPixelFormat::from_raw(rect.format);
+    decode(fmt, &buf[pixel_offset(rect, x, y)..])
+}
+
+/// 写入图像区域中 `(x, y)` 处的像素。
+pub fn write(buf: &mut [u8], rect: &RgaRect, x: i32, y: i32, rgba: [u8; 4]) {
+    let fmt = RgaPixelFormat::from_raw(rect.format);
+    let offset = pixel_offset(rect, x, y);
+    encode(fmt, rgba, &mut buf[offset..]);
+}
+
+/// 一个描述 YUV 图像中单个平面的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Plane {
+    /// 平面起始字节偏移。
+    pub offset: usize,
+    /// 一行的字节跨度。
+    pub stride: usize,
+    /// 水平及垂直采样间隔。
+    pub div: (i32, i32),
+    /// 每个采样点交错存放的分量数。
+    pub channels: usize,
+}
+
+/// 返回 8 位平面或半平面 YUV 格式的各个平面，其它格式返回 None。
+pub(crate) fn yuv_planes(rect: &RgaRect) -> Option<Vec<Plane>> {
+    use RgaPixelFormat::*;
+    let fmt = RgaPixelFormat::from_raw(rect.format);
+    let (ws, hs) = (rect.wstride as usize, rect.hstride as usize);
+    let luma = Plane {
+        offset: 0,
+        stride: ws,
+        div: (1, 1),
+        channels: 1,
+    };
+    let (semi, (dx, dy)) = match fmt {
+        YCbCr400 => return Some(vec![luma]),
+        YCbCr420sp | YCrCb420sp | YCbCr422sp | YCrCb422sp => (true, fmt.chroma_subsampling()?),
+        YCbCr420p | YCrCb420p | YCbCr422p | YCrCb422p => (false, fmt.chroma_subsampling()?),
+        _ => return None,
+    };
+    let chroma_rows = hs / dy as usize;
+    if semi {
+        Some(vec![
+            luma,
+            Plane {
+                offset: ws * hs,
+                stride: ws,
+                div: (dx, dy),
+                channels: 2,
+            },
+        ])
+    } else {
+        let stride = ws / dx as usize;
+        let plane = |offset| Plane {
+            offset,
+            stride,
+            div: (dx, dy),
+            channels: 1,
+        };
+        Some(vec![
+            luma,
+            plane(ws * hs),
+            plane(ws * hs + stride * chroma_rows),
+        ])
+    }
+}
+
+/// 返回图像所占用的字节数。
+pub(crate) fn image_len(rect: &RgaRect) -> usize {
+    let bpp = RgaPixelFormat::from_raw(rect.format).bits_per_pixel();
+    rect.wstride as usize * rect.hstride as usize * bpp / 8
+}
+
+/// 返回操作信息所描述图像的可读写字节数组，未映射到用户空间时返回 None。
+///
+/// # Safety
+/// 调用者需保证 `info.virAddr` 指向的内存至少覆盖整个图像且在使用期间有效。
+pub(crate) unsafe fn info_bytes_mut<'a>(info: &RgaInfo) -> Option<&'a mut [u8]> {
+    #[cfg(feature = "v1_4_0")]
+    if info.rd_mode > 1 {
+        return None;
+    }
+    if info.virAddr.is_null() {
+        None
+    } else {
+        Some(std::slice::from_raw_parts_mut(
+            info.virAddr as *mut u8,
+            image_len(&info.rect),
+        ))
+    }
+}
+
+/// 当 [`mosaic`] 支持此格式时返回 true。
+pub fn mosaic_supported(fmt: RgaPixelFormat) -> bool {
+    let rect = RgaRectBuilder::new().size(2, 2).format(fmt).build();
+    is_supported(fmt) || yuv_planes(&rect).is_some()
+}
+
+/// 将图像中的 `region` 区域以 `block` 大小的块进行马赛克处理，每块取各分量的均值。
+///
+/// 块以区域左上角为起点排列，区域边缘不足一块的部分单独取均值，`block` 不为正数时返回错误。
+///
+/// # Panics
+/// 当格式不受支持时触发，可使用 [`mosaic_supported`] 检查。
+pub fn mosaic(buf: &mut [u8], rect: &RgaRect, region: &Rect, block: i32) -> Result<(), io::Error> {
+    if block <= 0 {
+        return Err(invalid_input("mosaic block size must be positive"));
+    }
+    let fmt = RgaPixelFormat::from_raw(rect.format);
+    if is_supported(fmt) {
+        for by in (region.y..region.bottom()).step_by(block as usize) {
+            for bx in (region.x..region.right()).step_by(block as usize) {
+                let (x1, y1) = (
+                    (bx + block).min(region.right()),
+                    (by + block).min(region.bottom()),
+                );
+                let mut acc = [0u32; 4];
+                for y in by..y1 {
+                    for x in bx..x1 {
+                        let px = read(buf, rect, x, y);
+                        for c in 0..4 {
+                            acc[c] += px[c] as u32;
+                        }
+                    }
+                }
+                let n = ((x1 - bx) * (y1 - by)) as u32;
+                let avg = acc.map(|v| ((v + n / 2) / n) as u8);
+                for y in by..y1 {
+                    for x in bx..x1 {
+                        write(buf, rect, x, y, avg);
+                    }
+                }
+            }
+        }
+    } else if let Some(planes) = yuv_planes(rect) {
+        for p in planes {
+            let (dx, dy) = p.div;
+            let region = Rect::new(
+                (rect.xoffset + region.x) / dx,
+                (rect.yoffset + region.y) / dy,
+                region.width / dx,
+                region.height / dy,
+            );
+            let (bw, bh) = ((block / dx).max(1), (block / dy).max(1));
+            for by in (region.y..region.bottom()).step_by(bh as usize) {
+                for bx in (region.x..region.right()).step_by(bw as usize) {
+                    let (x1, y1) = (
+                        (bx + bw).min(region.right()),
+                        (by + bh).min(region.bottom()),
+                    );
+                    let index = |x: i32, y: i32, c: usize| {
+                        p.offset + y as usize * p.stride + x as usize * p.channels + c
+                    };
+                    let n = ((x1 - bx) * (y1 - by)) as u32;
+                    for c in 0..p.channels {
+                        let mut acc = 0u32;
+                        for y in by..y1 {
+                            for x in bx..x1 {
+                                acc += buf[index(x, y, c)] as u32;
+                            }
+                        }
+                        let avg = ((acc + n / 2) / n) as u8;
+                        for y in by..y1 {
+                            for x in bx..x1 {
+                                buf[index(x, y, c)] = avg;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    } else {
+        panic!("soft: unsupported format {:?}", fmt);
+    }
+    Ok(())
+}
+
+/// 返回 Catmull-Rom 三次卷积核在 `t` 处的权重。
+fn cubic(t: f32) -> f32 {
+    let t = t.abs();
+    if t < 1.0 {
+        1.5 * t * t * t - 2.5 * t * t + 1.0
+    } else if t < 2.0 {
+        -0.5 * t * t * t + 2.5 * t * t - 4.0 * t + 2.0
+    } else {
+        0.0
+    }
+}
+
+/// 返回目标坐标 `d` 对应的源像素索引及权重，源坐标按像素中心对齐。
+fn taps(d: i32, src_len: i32, dst_len: i32, filter: ScaleFilter) -> Vec<(i32, f32)> {
+    let ratio = src_len as f32 / dst_len as f32;
+    let clamp = |i: i32| i.clamp(0, src_len - 1);
+    match filter {
+        ScaleFilter::Nearest => {
+            vec![(clamp(((d as f32 + 0.5) * ratio) as i32), 1.0)]
+        }
+        ScaleFilter::Bilinear => {
+            let f = ((d as f32 + 0.5) * ratio - 0.5).max(0.0);
+            let i = f.floor() as i32;
+            let t = f - i as f32;
+            vec![(clamp(i), 1.0 - t), (clamp(i + 1), t)]
+        }
+        ScaleFilter::Bicubic => {
+            let f = (d as f32 + 0.5) * ratio - 0.5;
+            let i = f.floor() as i32;
+            (i - 1..=i + 2)
+                .map(|k| (clamp(k), cubic(f - k as f32)))
+                .collect()
+        }
+    }
+}
+
+/// 使用指定的插值方式将 `src` 图像缩放到 `dst` 图像。
+///
+/// # Panics
+/// 当格式不受支持时触发。
+pub fn scale(
+    src: &[u8],
+    src_rect: &RgaRect,
+    dst: &mut [u8],
+    dst_rect: &RgaRect,
+    filter: ScaleFilter,
+) {
+    let xtaps: Vec<_> = (0..dst_rect.width)
+        .map(|x| taps(x, src_rect.width, dst_rect.width, filter))
+        .collect();
+    for y in 0..dst_rect.height {
+        let ytaps = taps(y, src_rect.height, dst_rect.height, filter);
+        for (x, xt) in xtaps.iter().enumerate() {
+            let mut acc = [0f32; 4];
+            for &(sy, wy) in &ytaps {
+                for &(sx, wx) in xt {
+                    let px = read(src, src_rect, sx, sy);
+                    for c in 0..4 {
+                        acc[c] += px[c] as f32 * wx * wy;
+                    }
+                }
+            }
+            let px = acc.map(|v| v.round().clamp(0.0, 255.0) as u8);
+            write(dst, dst_rect, x as i32, y, px);
+        }
+    }
+}
+
+/// 将非预乘的 RGBA 像素转换为预乘形式，各颜色分量为 `(c * a + 127) / 255`。
+pub fn premultiply_pixel(px: [u8; 4]) -> [u8; 4] {
+    let a = px[3] as u32;
+    let mul = |c: u8| ((c as u32 * a + 127) / 255) as u8;
+    [mul(px[0]), mul(px[1]), mul(px[2]), px[3]]
+}
+
+/// 将预乘的 RGBA 像素还原为非预乘形式，各颜色分量为 `(c * 255 + a / 2) / a`，
+/// 结果截断到 255，Alpha 为 0 时颜色为 0。
+pub fn unpremultiply_pixel(px: [u8; 4]) -> [u8; 4] {
+    let a = px[3] as u32;
+    if a == 0 {
+        return [0; 4];
+    }
+    let div = |c: u8| ((c as u32 * 255 + a / 2) / a).min(255) as u8;
+    [div(px[0]), div(px[1]), div(px[2]), px[3]]
+}
+
+/// 将 `src` 图像逐像素转换到 `dst` 图像，两者大小须一致。
+fn map_pixels(
+    src: &[u8],
+    src_rect: &RgaRect,
+    dst: &mut [u8],
+    dst_rect: &RgaRect,
+    f: impl Fn([u8; 4]) -> [u8; 4],
+) {
+    for y in 0..dst_rect.height {
+        for x in 0..dst_rect.width {
+            write(dst, dst_rect, x, y, f(read(src, src_rect, x, y)));
+        }
+    }
+}
+
+/// 将 `src` 图像预乘 Alpha 后写入 `dst` 图像。
+///
+/// # Panics
+/// 当格式不受支持时触发。
+pub fn premultiply(src: &[u8], src_rect: &RgaRect, dst: &mut [u8], dst_rect: &RgaRect) {
+    map_pixels(src, src_rect, dst, dst_rect, premultiply_pixel)
+}
+
+/// 将 `src` 图像反预乘 Alpha 后写入 `dst` 图像。
+///
+/// # Panics
+/// 当格式不受支持时触发。
+pub fn unpremultiply(src: &[u8], src_rect: &RgaRect, dst: &mut [u8], dst_rect: &RgaRect) {
+    map_pixels(src, src_rect, dst, dst_rect, unpremultiply_pixel)
+}
+
+/// 以全局 Alpha 混合两个像素，各分量为 `(b * alpha + a * (255 - alpha) + 127) / 255`。
+pub fn crossfade_pixel(a: [u8; 4], b: [u8; 4], alpha: u8) -> [u8; 4] {
+    let (wb, wa) = (alpha as u32, 255 - alpha as u32);
+    let mut out = [0u8; 4];
+    for (i, o) in out.iter_mut().enumerate() {
+        *o = ((b[i] as u32 * wb + a[i] as u32 * wa + 127) / 255) as u8;
+    }
+    out
+}
+
+/// 以全局 Alpha 混合 `a`、`b` 两幅图像写入 `dst` 图像，三者大小须一致。
+///
+/// 逐像素 Alpha 作为普通分量参与混合，不影响混合权重。
+///
+/// # Panics
+/// 当格式不受支持时触发。
+pub fn crossfade(
+    a: &[u8],
+    a_rect: &RgaRect,
+    b: &[u8],
+    b_rect: &RgaRect,
+    dst: &mut [u8],
+    dst_rect: &RgaRect,
+    alpha: u8,
+) {
+    for y in 0..dst_rect.height {
+        for x in 0..dst_rect.width {
+            let px = crossfade_pixel(read(a, a_rect, x, y), read(b, b_rect, x, y), alpha);
+            write(dst, dst_rect, x, y, px);
+        }
+    }
+}
+
+/// 以非预乘 SRC_OVER 模式将 `src` 像素叠加到 `dst` 像素上。
+///
+/// 颜色分量为 `(s * a + d * (255 - a) + 127) / 255`，Alpha 为 `a + da * (255 - a) / 255`。
+pub fn blend_over_pixel(src: [u8; 4], dst: [u8; 4]) -> [u8; 4] {
+    let a = src[3];
+    let mut out = crossfade_pixel(dst, src, a);
+    out[3] = (a as u32 + (dst[3] as u32 * (255 - a as u32) + 127) / 255) as u8;
+    out
+}
+
+/// 以非预乘 SRC_OVER 模式将 `src` 图像叠加到大小一致的 `dst` 图像上。
+///
+/// # Panics
+/// 当格式不受支持时触发。
+pub fn blend_over(src: &[u8], src_rect: &RgaRect, dst: &mut [u8], dst_rect: &RgaRect) {
+    for y in 0..dst_rect.height {
+        for x in 0..dst_rect.width {
+            let px = blend_over_pixel(read(src, src_rect, x, y), read(dst, dst_rect, x, y));
+            write(dst, dst_rect, x, y, px);
+        }
+    }
+}
+
+/// 将 `src` 图像按 `orient` 变换后写入 `dst` 图像，`dst` 的大小须为变换后的大小。
+///
+/// # Panics
+/// 当格式不受支持时触发。
+pub fn rotate(
+    src: &[u8],
+    src_rect: &RgaRect,
+    dst: &mut [u8],
+    dst_rect: &RgaRect,
+    orient: RgaOrientation,
+) {
+    let (w, h) = (src_rect.width, src_rect.height);
+    for y in 0..h {
+        for x in 0..w {
+            let (dx, dy) = orient.map_point(x, y, w, h);
+            write(dst, dst_rect, dx, dy, read(src, src_rect, x, y));
+        }
+    }
+}
+
+/// 一个描述 CPU 可访问图像的类型，用于测试及参考实现。
+#[derive(Clone, Debug)]
+pub struct SoftImage {
+    pub rect: RgaRect,
+    pub data: Vec<u8>,
+}
+
+impl SoftImage {
+    /// 创建一个指定大小及格式的全 0 图像。
+    pub fn new(width: i32, height: i32, format: RgaPixelFormat) -> Self {
+        let rect = RgaRectBuilder::new()
+            .size(width, height)
+            .format(format)
+            .build();
+        Self::with_rect(rect)
+    }
+
+    /// 为指定的图像区域创建一个全 0 图像。
+    pub fn with_rect(rect: RgaRect) -> Self {
+        let fmt = RgaPixelFormat::from_raw(rect.format);
+        let len = rect.wstride as usize * rect.hstride as usize * fmt.bits_per_pixel() / 8;
+        Self {
+            rect,
+            data: vec![0; len],
+        }
+    }
+
+    /// 返回图像宽度。
+    pub fn width(&self) -> i32 {
+        self.rect.width
+    }
+
+    /// 返回图像高度。
+    pub fn height(&self) -> i32 {
+        self.rect.height
+    }
+
+    /// 返回像素格式。
+    pub fn format(&self) -> RgaPixelFormat {
+        RgaPixelFormat::from_raw(self.rect.format)
+    }
+
+    /// 读取 `(x, y)` 处的像素。
+    pub fn get(&self, x: i32, y: i32) -> [u8; 4] {
+        read(&self.data, &self.rect, x, y)
+    }
+
+    /// 写入 `(x, y)` 处的像素。
+    pub fn put(&mut self, x: i32, y: i32, rgba: [u8; 4]) {
+        write(&mut self.data, &self.rect, x, y, rgba)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codec_roundtrip() {
+        let px = [0xf8, 0xfc, 0xf8, 0xff];
+        let mut buf = [0u8; 4];
+        encode(RgaPixelFormat::Rgb565, px, &mut buf);
+        assert_eq!(u16::from_le_bytes([buf[0], buf[1]]), 0xffff);
+        assert_eq!(
+            decode(RgaPixelFormat::Rgb565, &buf),
+            [0xff, 0xff, 0xff, 0xff]
+        );
+        encode(RgaPixelFormat::Rgb565, [0x80, 0x40, 0x20, 0], &mut buf);
+        assert_eq!(
+            u16::from_le_bytes([buf[0], buf[1]]),
+            (0x10 << 11) | (0x10 << 5) | 0x04
+        );
+        encode(RgaPixelFormat::Bgra8888, [1, 2, 3, 4], &mut buf);
+        assert_eq!(buf, [3, 2, 1, 4]);
+        assert_eq!(decode(RgaPixelFormat::Bgra8888, &buf), [1, 2, 3, 4]);
+        encode(RgaPixelFormat::Rgba4444, [0x10, 0x20, 0x30, 0x40], &mut buf);
+        assert_eq!(
+            decode(RgaPixelFormat::Rgba4444, &buf),
+            [0x11, 0x22, 0x33, 0x44]
+        );
+    }
+
+    #[test]
+    fn test_soft_image() {
+        let mut img = SoftImage::new(4, 2, RgaPixelFormat::Rgb888);
+        assert_eq!(img.data.len(), 4 * 2 * 3);
+        img.put(3, 1, [1, 2, 3, 255]);
+        assert_eq!(&img.data[21..], &[1, 2, 3]);
+        assert_eq!(img.get(3, 1), [1, 2, 3, 255]);
+    }
+
+    #[test]
+    fn test_mosaic_rgba() {
+        let mut img = SoftImage::new(8, 8, RgaPixelFormat::Rgba8888);
+        for y in 0..8 {
+            for x in 0..8 {
+                img.put(x, y, [(x * 10) as u8, (y * 10) as u8, 0, 255]);
+            }
+        }
+        let orig = img.clone();
+        mosaic(&mut img.data, &orig.rect, &Rect::new(2, 2, 6, 4), 4).unwrap();
+        // 第一块 (2..6, 2..6) 的均值为 (35, 35)。
+        for y in 2..6 {
+            for x in 2..6 {
+                assert_eq!(img.get(x, y), [35, 35, 0, 255]);
+            }
+        }
+        // 边缘不足一块的 (6..8, 2..6) 单独取均值。
+        assert_eq!(img.get(7, 5), [65, 35, 0, 255]);
+        assert_eq!(img.get(1, 1), orig.get(1, 1));
+        assert_eq!(img.get(7, 6), orig.get(7, 6));
+        assert!(mosaic(&mut img.data, &orig.rect, &Rect::new(2, 2, 6, 4), 0).is_err());
+        assert_eq!(img.get(1, 1), orig.get(1, 1));
+    }
+
+    #[test]
+    fn test_mosaic_nv12() {
+        let mut img = SoftImage::new(8, 4, RgaPixelFormat::YCbCr420sp);
+        assert_eq!(img.data.len(), 8 * 4 * 3 / 2);
+        for (i, v) in img.data.iter_mut().enumerate() {
+            *v = i as u8;
+        }
+        let rect = img.rect;
+        mosaic(&mut img.data, &rect, &Rect::new(0, 0, 4, 4), 4).unwrap();
+        // Y: 0..4 x 0..4 的均值为 (0+1+2+3 + 8.. + 24..27) / 16 = 13.5。
+        assert_eq!(img.data[0], 14);
+        assert_eq!(img.data[27], 14);
+        assert_eq!(img.data[4], 4);
+        // UV: 行 0、1 的前两个采样点，U 为 (32+34+40+42)/4，V 为 (33+35+41+43)/4。
+        assert_eq!(&img.data[32..36], &[37, 38, 37, 38]);
+        assert_eq!(&img.data[40..44], &[37, 38, 37, 38]);
+        assert_eq!(img.data[36], 36);
+    }
+
+    #[test]
+    fn test_scale_filters() {
+        let mut src = SoftImage::new(8, 4, RgaPixelFormat::Rgba8888);
+        for y in 0..4 {
+            for x in 0..8 {
+                src.put(x, y, [(x * 32) as u8, 200, 0, 255]);
+            }
+        }
+        let mut dst = SoftImage::new(16, 8, RgaPixelFormat::Rgba8888);
+
+        scale(
+            &src.data,
+            &src.rect,
+            &mut dst.data,
+            &dst.rect,
+            ScaleFilter::Nearest,
+        );
+        for x in 0..16 {
+            assert_eq!(dst.get(x, 5), src.get(x / 2, 2));
+        }
+
+        // 线性渐变经双线性及双三次插值后，内部像素仍为线性渐变。
+        for filter in [ScaleFilter::Bilinear, ScaleFilter::Bicubic] {
+            scale(&src.data, &src.rect, &mut dst.data, &dst.rect, filter);
+            for x in 2..14 {
+                let expected = ((x as f32 + 0.5) / 2.0 - 0.5) * 32.0;
+                let px = dst.get(x, 3);
+                assert!((px[0] as f32 - expected).abs() <= 1.0, "{:?} {}", filter, x);
+                assert_eq!(px[1..], [200, 0, 255]);
+            }
+        }
+
+        let mut half = SoftImage::new(4, 2, RgaPixelFormat::Rgba8888);
+        scale(
+            &src.data,
+            &src.rect,
+            &mut half.data,
+            &half.rect,
+            ScaleFilter::Bilinear,
+        );
+        assert_eq!(half.get(1, 1), [80, 200, 0, 255]);
+    }
+}
@@ -0,0 +1,498 @@
+//! 超出硬件尺寸限制的图像的分块处理。
+//!
+//! 缩放时分块边界选在源、目标坐标均为整数的位置，使每个分块的缩放比例及采样相位与整幅图像一致。
+//! 分块向外扩展插值核所需的重叠区域后缩放到临时缓冲区，再将内部区域复制到目标图像，
+//! 因此分块之间不会产生接缝。缩放比例使这样的边界无法落在限制以内时，
+//! 改用非整数的源边界并加大重叠区域，采样位置与整幅缩放相差不超过对齐单位个源像素。分块处理总是以同步模式逐块提交。
+use super::geom::{bounds_of, with_region};
+use super::{ffi, CoreMask, Rect, Rga, RgaInfo, RgaOrientation, RgaPixelFormat};
+use super::{RgaRectBuilder, ScaleFilter};
+use std::io;
+
+/// 一个描述单个分块的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Tile {
+    /// 读取的源图像区域，含重叠部分。
+    pub src: Rect,
+    /// 源图像区域处理后对应的目标图像区域，含重叠部分。
+    pub out: Rect,
+    /// 写入目标图像的区域，位于 `out` 之内。
+    pub dst: Rect,
+}
+
+impl Tile {
+    /// 当分块含重叠部分、需经临时缓冲区写入目标图像时返回 true。
+    pub fn has_overlap(&self) -> bool {
+        self.out != self.dst
+    }
+}
+
+/// 一个描述分块规划参数的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TilePlanner {
+    /// 单次操作允许的最大输入宽高。
+    pub max_input: (i32, i32),
+    /// 单次操作允许的最大输出宽高。
+    pub max_output: (i32, i32),
+    /// 分块边界的对齐要求，YUV 图像为 2。
+    pub align: i32,
+}
+
+/// 一维分块：`(源区间, 输出区间, 写入区间)`，均为 `(起点, 长度)`。
+type Span = [(i32, i32); 3];
+
+impl TilePlanner {
+    /// 创建分块规划参数。
+    pub fn new(max_input: (i32, i32), max_output: (i32, i32), align: i32) -> Self {
+        Self {
+            max_input,
+            max_output,
+            align: align.max(1),
+        }
+    }
+
+    /// 当 `width` x `height` 的源图像超出输入限制时返回 true。
+    pub fn exceeds_input(&self, width: i32, height: i32) -> bool {
+        width > self.max_input.0 || height > self.max_input.1
+    }
+
+    /// 当 `width` x `height` 的目标图像超出输出限制时返回 true。
+    pub fn exceeds_output(&self, width: i32, height: i32) -> bool {
+        width > self.max_output.0 || height > self.max_output.1
+    }
+
+    /// 规划将 `src` 大小的图像缩放到 `dst` 大小的分块。
+    ///
+    /// 限制过小、连插值核所需的重叠区域都无法容纳时返回 `Unsupported` 错误。
+    pub fn scale(
+        &self,
+        src: (i32, i32),
+        dst: (i32, i32),
+        filter: ScaleFilter,
+    ) -> Result<Vec<Tile>, io::Error> {
+        let radius = filter_radius(filter);
+        let (mi, mo) = (self.max_input, self.max_output);
+        let xs = plan_axis(src.0, dst.0, (mi.0, mo.0), radius, self.align);
+        let ys = plan_axis(src.1, dst.1, (mi.1, mo.1), radius, self.align);
+        match (xs, ys) {
+            (Some(xs), Some(ys)) => Ok(ys
+                .iter()
+                .flat_map(|y| xs.iter().map(move |x| span_tile(x, y)))
+                .collect()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "could not split {}x{} -> {}x{} into tiles within {}x{} -> {}x{}",
+                    src.0, src.1, dst.0, dst.1, mi.0, mi.1, mo.0, mo.1
+                ),
+            )),
+        }
+    }
+
+    /// 规划对 `width` x `height` 的图像进行旋转或翻转的分块，目标区域按变换重新映射。
+    pub fn rotate(&self, width: i32, height: i32, orient: RgaOrientation) -> Vec<Tile> {
+        // 旋转 90 度时源分块的宽高互换，因此取输入、输出宽高中最小的限制。
+        let (mi, mo) = (self.max_input, self.max_output);
+        let side = mi.0.min(mi.1).min(mo.0).min(mo.1);
+        let side = (side - side % self.align).max(self.align);
+        self.grid(width, height, (side, side))
+            .into_iter()
+            .map(|src| {
+                let (x0, y0) = orient.map_point(src.x, src.y, width, height);
+                let (x1, y1) =
+                    orient.map_point(src.x + src.width - 1, src.y + src.height - 1, width, height);
+                let (w, h) = orient.map_size(src.width, src.height);
+                let dst = Rect::new(x0.min(x1), y0.min(y1), w, h);
+                Tile { src, out: dst, dst }
+            })
+            .collect()
+    }
+
+    /// 规划填充 `width` x `height` 的图像的分块。
+    pub fn fill(&self, width: i32, height: i32) -> Vec<Tile> {
+        let (mw, mh) = self.max_output;
+        let size = (
+            (mw - mw % self.align).max(self.align),
+            (mh - mh % self.align).max(self.align),
+        );
+        self.grid(width, height, size)
+            .into_iter()
+            .map(|r| Tile {
+                src: r,
+                out: r,
+                dst: r,
+            })
+            .collect()
+    }
+
+    fn grid(&self, width: i32, height: i32, (tw, th): (i32, i32)) -> Vec<Rect> {
+        let mut rects = Vec::new();
+        for y in (0..height).step_by(th as usize) {
+            for x in (0..width).step_by(tw as usize) {
+                rects.push(Rect::new(x, y, tw.min(width - x), th.min(height - y)));
+            }
+        }
+        rects
+    }
+}
+
+/// 返回参考实现中插值核向两侧读取的源像素数。
+fn filter_radius(filter: ScaleFilter) -> i64 {
+    match filter {
+        ScaleFilter::Nearest => 0,
+        ScaleFilter::Bilinear => 1,
+        ScaleFilter::Bicubic => 2,
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn span_tile(x: &Span, y: &Span) -> Tile {
+    let rect = |i: usize| Rect::new(x[i].0, y[i].0, x[i].1, y[i].1);
+    Tile {
+        src: rect(0),
+        out: rect(1),
+        dst: rect(2),
+    }
+}
+
+/// 规划长度为 `s` 的源区间缩放到长度为 `d` 的目标区间的一维分块，无法规划时返回 None。
+///
+/// `(max_in, max_out)` 分别限制每个分块读取的源区间及输出区间的长度。
+fn plan_axis(
+    s: i32,
+    d: i32,
+    (max_in, max_out): (i32, i32),
+    radius: i64,
+    align: i32,
+) -> Option<Vec<Span>> {
+    if s <= max_in && d <= max_out {
+        return Some(vec![[(0, s), (0, d), (0, d)]]);
+    }
+    let (s, d, a) = (s as i64, d as i64, align as i64);
+    let (max_in, max_out) = (max_in as i64, max_out as i64);
+    let ceil_div = |x: i64, y: i64| (x + y - 1) / y;
+    // 目标坐标为 `g` 的倍数时，对应的源坐标为整数且两者均满足对齐要求。
+    let g = d / gcd(s, d) * a;
+    let margin = ceil_div(ceil_div(radius * d, s), g) * g;
+    let limit = max_out.min(max_in * d / s);
+    let step = (limit - 2 * margin) / g * g;
+    if step > 0 {
+        return Some(spans(d, step, margin, |o| o * s / d, |o| o * s / d));
+    }
+    // 边界无法对齐时源边界向外取整，多出的采样相位误差由额外一个源像素的重叠区域吸收。
+    let margin = ceil_div(ceil_div((radius + 1) * d, s), a) * a;
+    let limit = max_out.min((max_in - 2 * a) * d / s);
+    let step = (limit - 2 * margin) / a * a;
+    if step <= 0 {
+        return None;
+    }
+    Some(spans(
+        d,
+        step,
+        margin,
+        |o| o * s / d / a * a,
+        |o| (ceil_div(ceil_div(o * s, d), a) * a).min(s),
+    ))
+}
+
+/// 以 `step` 为间隔划分长度为 `d` 的目标区间，各分块向两侧扩展 `margin`，
+/// 由 `start`、`end` 计算输出区间对应的源区间起点及终点。
+fn spans(
+    d: i64,
+    step: i64,
+    margin: i64,
+    start: impl Fn(i64) -> i64,
+    end: impl Fn(i64) -> i64,
+) -> Vec<Span> {
+    (0..d)
+        .step_by(step as usize)
+        .map(|x0| {
+            let x1 = (x0 + step).min(d);
+            let (o0, o1) = ((x0 - margin).max(0), (x1 + margin).min(d));
+            let (s0, s1) = (start(o0), end(o1));
+            [
+                (s0 as i32, (s1 - s0) as i32),
+                (o0 as i32, (o1 - o0) as i32),
+                (x0 as i32, (x1 - x0) as i32),
+            ]
+        })
+        .collect()
+}
+
+impl Rga {
+    /// 返回适用于 `src`、`dst` 两幅图像的分块规划参数。
+    pub(crate) fn tile_planner(&self, src: &RgaInfo, dst: &RgaInfo) -> TilePlanner {
+        let caps = self.capabilities();
+        let min = |(a, b): (i32, i32), (c, d): (i32, i32)| (a.min(c), b.min(d));
+        let (sm, dm) = (CoreMask::of(src), CoreMask::of(dst));
+        let input = min(caps.max_size_on(sm), caps.max_size_on(dm));
+        let output = min(caps.max_output_size_on(sm), caps.max_output_size_on(dm));
+        let yuv = [src, dst]
+            .iter()
+            .any(|i| RgaPixelFormat::from_raw(i.rect.format).is_yuv());
+        TilePlanner::new(input, output, if yuv { 2 } else { 1 })
+    }
+
+    /// 当 `src` 或 `dst` 超出硬件尺寸限制、需要分块处理时返回 true。
+    pub(crate) fn needs_tiling(&self, src: &RgaInfo, dst: &RgaInfo) -> bool {
+        let planner = self.tile_planner(src, dst);
+        planner.exceeds_input(src.rect.width, src.rect.height)
+            || planner.exceeds_output(dst.rect.width, dst.rect.height)
+    }
+
+    /// 分块对象图像进行缩放或色彩空间转换操作。
+    pub(crate) fn scale_tiled(&self, src: &RgaInfo, dst: &mut RgaInfo) -> Result<(), io::Error> {
+        check_tiling(src, dst)?;
+        if src.rotation != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "tiled scaling could not be combined with rotation",
+            ));
+        }
+        let (s, d) = (bounds_of(src)?, bounds_of(dst)?);
+        let filter = ScaleFilter::from_raw(src.scale_mode).unwrap_or_default();
+        let tiles =
+            self.tile_planner(src, dst)
+                .scale((s.width, s.height), (d.width, d.height), filter)?;
+        let fmt = RgaPixelFormat::from_raw(dst.rect.format);
+        for tile in &tiles {
+            let src = with_region(src, &tile.src);
+            let mut out = tile_dst(dst, &tile.dst);
+            if !tile.has_overlap() {
+                self.blit(&src, &mut out, None)?;
+                continue;
+            }
+            let rect = RgaRectBuilder::new()
+                .size(tile.out.width, tile.out.height)
+                .format(fmt)
+                .build();
+            let mut scratch = self.scratch.take(&rect)?;
+            let mut tmp = scratch.info(rect, dst);
+            self.blit(&src, &mut tmp, None)?;
+            let inner = Rect::new(
+                tile.dst.x - tile.out.x,
+                tile.dst.y - tile.out.y,
+                tile.dst.width,
+                tile.dst.height,
+            );
+            self.blit(&with_region(&tmp, &inner), &mut out, None)?;
+        }
+        Ok(())
+    }
+
+    /// 分块对象图像进行旋转或翻转操作。
+    pub(crate) fn rotate_tiled(
+        &self,
+        src: &RgaInfo,
+        dst: &mut RgaInfo,
+        orient: RgaOrientation,
+    ) -> Result<(), io::Error> {
+        check_tiling(src, dst)?;
+        let (s, d) = (bounds_of(src)?, bounds_of(dst)?);
+        if orient.map_size(s.width, s.height) != (d.width, d.height) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "tiled rotation could not be combined with scaling",
+            ));
+        }
+        let src = RgaInfo {
+            rotation: orient.to_raw(),
+            ..*src
+        };
+        for tile in self
+            .tile_planner(&src, dst)
+            .rotate(s.width, s.height, orient)
+        {
+            let mut out = tile_dst(dst, &tile.dst);
+            self.blit(&with_region(&src, &tile.src), &mut out, None)?;
+        }
+        Ok(())
+    }
+
+    /// 分块使用指定颜色填充图像。
+    pub(crate) fn fill_tiled(&self, dst: &RgaInfo, color: u32) -> Result<(), io::Error> {
+        check_tiling(dst, dst)?;
+        let d = bounds_of(dst)?;
+        for tile in self.tile_planner(dst, dst).fill(d.width, d.height) {
+            self.fill(&tile_dst(dst, &tile.dst), color)?;
+        }
+        Ok(())
+    }
+}
+
+/// 检查图像能否按区域分块，压缩或分块存储的图像无法分块。
+fn check_tiling(src: &RgaInfo, dst: &RgaInfo) -> Result<(), io::Error> {
+    #[cfg(feature = "v1_4_0")]
+    if src.rd_mode > 1 || dst.rd_mode > 1 {
+        return Err(super::geom::invalid_input(
+            "tiled processing requires raster layout",
+        ));
+    }
+    #[cfg(not(feature = "v1_4_0"))]
+    let _ = (src, dst);
+    Ok(())
+}
+
+/// 返回写入目标图像 `region` 区域的同步操作信息。
+fn tile_dst(dst: &RgaInfo, region: &Rect) -> RgaInfo {
+    let mut info = with_region(dst, region);
+    info.sync_mode = ffi::RGA_BLIT_SYNC as i32;
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::soft::{self, SoftImage};
+
+    fn pattern(width: i32, height: i32) -> SoftImage {
+        let mut img = SoftImage::new(width, height, RgaPixelFormat::Rgba8888);
+        for y in 0..height {
+            for x in 0..width {
+                let v = (x * 37 + y * 91) as u8;
+                img.put(x, y, [v, v ^ 0x5a, (x * y) as u8, 255 - v]);
+            }
+        }
+        img
+    }
+
+    fn region(img: &SoftImage, r: &Rect) -> crate::RgaRect {
+        crate::RgaRect {
+            xoffset: r.x,
+            yoffset: r.y,
+            width: r.width,
+            height: r.height,
+            ..img.rect
+        }
+    }
+
+    #[test]
+    fn test_tiled_scale_matches_reference() {
+        let planner = TilePlanner::new((16, 16), (16, 16), 1);
+        let src = pattern(48, 36);
+        for (dw, dh) in [(96, 72), (32, 24)] {
+            for filter in [
+                ScaleFilter::Nearest,
+                ScaleFilter::Bilinear,
+                ScaleFilter::Bicubic,
+            ] {
+                let mut full = SoftImage::new(dw, dh, RgaPixelFormat::Rgba8888);
+                soft::scale(&src.data, &src.rect, &mut full.data, &full.rect, filter);
+
+                let tiles = planner.scale((48, 36), (dw, dh), filter).unwrap();
+                assert!(tiles.len() > 1);
+                let mut tiled = SoftImage::new(dw, dh, RgaPixelFormat::Rgba8888);
+                let mut covered = 0;
+                for t in &tiles {
+                    assert!(t.src.width <= 16 && t.src.height <= 16);
+                    assert!(t.out.width <= 16 && t.out.height <= 16);
+                    let mut tmp =
+                        SoftImage::new(t.out.width, t.out.height, RgaPixelFormat::Rgba8888);
+                    let src_rect = region(&src, &t.src);
+                    soft::scale(&src.data, &src_rect, &mut tmp.data, &tmp.rect, filter);
+                    for y in 0..t.dst.height {
+                        for x in 0..t.dst.width {
+                            let px = tmp.get(t.dst.x - t.out.x + x, t.dst.y - t.out.y + y);
+                            tiled.put(t.dst.x + x, t.dst.y + y, px);
+                        }
+                    }
+                    covered += t.dst.width * t.dst.height;
+                }
+                assert_eq!(covered, dw * dh);
+                assert_eq!(tiled.data, full.data, "{:?} {}x{}", filter, dw, dh);
+            }
+        }
+        // 限制连重叠区域都无法容纳时无法分块。
+        assert!(TilePlanner::new((4, 4), (4, 4), 1)
+            .scale((48, 36), (96, 72), ScaleFilter::Bicubic)
+            .is_err());
+    }
+
+    #[test]
+    fn test_tiled_scale_coprime() {
+        // 互质的缩放比例无法对齐分块边界，改用非整数边界及额外的重叠区域，
+        // 采样位置的偏差小于对齐单位个源像素。
+        let planner = TilePlanner::new((16, 16), (12, 12), 2);
+        let (s, d) = ((41, 30), (40, 22));
+        let mut src = SoftImage::new(s.0, s.1, RgaPixelFormat::Rgba8888);
+        for y in 0..s.1 {
+            for x in 0..s.0 {
+                src.put(x, y, [(x * 2) as u8, (y * 3) as u8, 128, 255]);
+            }
+        }
+        let filter = ScaleFilter::Bilinear;
+        let mut full = SoftImage::new(d.0, d.1, RgaPixelFormat::Rgba8888);
+        soft::scale(&src.data, &src.rect, &mut full.data, &full.rect, filter);
+
+        let tiles = planner.scale(s, d, filter).unwrap();
+        let mut covered = 0;
+        for t in &tiles {
+            assert!(t.src.width <= 16 && t.src.height <= 16);
+            assert!(t.out.width <= 12 && t.out.height <= 12);
+            assert_eq!(
+                (t.src.x % 2, t.src.y % 2, t.dst.x % 2, t.dst.y % 2),
+                (0, 0, 0, 0)
+            );
+            let mut tmp = SoftImage::new(t.out.width, t.out.height, RgaPixelFormat::Rgba8888);
+            let src_rect = region(&src, &t.src);
+            soft::scale(&src.data, &src_rect, &mut tmp.data, &tmp.rect, filter);
+            for y in 0..t.dst.height {
+                for x in 0..t.dst.width {
+                    let px = tmp.get(t.dst.x - t.out.x + x, t.dst.y - t.out.y + y);
+                    let expected = full.get(t.dst.x + x, t.dst.y + y);
+                    for c in 0..4 {
+                        assert!(px[c].abs_diff(expected[c]) <= 8, "{:?} {:?}", px, expected);
+                    }
+                }
+            }
+            covered += t.dst.width * t.dst.height;
+        }
+        assert_eq!(covered, d.0 * d.1);
+        let rga2 = TilePlanner::new((4096, 4096), (4096, 4096), 2);
+        let tiles = rga2.scale((10001, 8), (4096, 8), filter).unwrap();
+        assert!(tiles.iter().all(|t| t.src.width <= 4096));
+        assert_eq!(tiles.iter().map(|t| t.dst.width).sum::<i32>(), 4096);
+    }
+
+    #[test]
+    fn test_tiled_rotate_remap() {
+        let planner = TilePlanner::new((4, 6), (6, 4), 2);
+        let (w, h) = (11, 7);
+        for orient in [
+            RgaOrientation::ROT_90,
+            RgaOrientation::ROT_180,
+            RgaOrientation::TRANSPOSE,
+            RgaOrientation::FLIP_V,
+        ] {
+            let tiles = planner.rotate(w, h, orient);
+            let mut seen = vec![false; (w * h) as usize];
+            for t in &tiles {
+                assert!(t.src.width <= 4 && t.src.height <= 4);
+                assert_eq!(t.src.x % 2, 0);
+                for ly in 0..t.src.height {
+                    for lx in 0..t.src.width {
+                        let (gx, gy) = (t.src.x + lx, t.src.y + ly);
+                        let (tx, ty) = orient.map_point(lx, ly, t.src.width, t.src.height);
+                        let expected = orient.map_point(gx, gy, w, h);
+                        assert_eq!((t.dst.x + tx, t.dst.y + ty), expected);
+                        seen[(gy * w + gx) as usize] = true;
+                    }
+                }
+            }
+            assert!(seen.iter().all(|s| *s));
+        }
+        let fill = planner.fill(9, 13);
+        assert_eq!(fill.len(), 2 * 4);
+        assert_eq!(
+            fill.iter().map(|t| t.dst.width * t.dst.height).sum::<i32>(),
+            9 * 13
+        );
+    }
+}